@@ -0,0 +1,182 @@
+//! Initialize-on-use with asynchronous initialization.
+
+use std::future::{self, Future};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::Poison;
+
+/// Initialize on use, asynchronously: a value that will be
+/// lazily initialized at first reference by awaiting the
+/// future returned by an initialization function.
+///
+/// The initialization future is stored in the [AsyncIou]
+/// itself, and is driven by whichever tasks are awaiting
+/// [AsyncIou::get]. Concurrent awaiters share one
+/// initialization, and dropping any awaiter, including the
+/// one that started initialization, neither loses the
+/// initialization nor stalls the other awaiters. No
+/// particular executor is required.
+///
+/// If the initialization function or future panics, the
+/// panic is propagated to the awaiter that was driving it
+/// and the [AsyncIou] is poisoned.
+pub struct AsyncIou<S, F, Fut, T> {
+    value: OnceLock<T>,
+    state: Mutex<AsyncIouState<S, F, Fut>>,
+    wakers: OnceLock<Arc<Wakers>>,
+}
+
+enum AsyncIouState<S, F, Fut> {
+    /// Not yet initialized.
+    PreInit(S, F),
+    /// Initialization future is in flight.
+    Running(Pin<Box<Fut>>),
+    /// Initialized.
+    Init,
+    /// Initialization panicked.
+    Poisoned(Poison),
+}
+
+/// Wakers of the tasks awaiting an [AsyncIou]. The
+/// initialization future is polled with a waker that wakes
+/// all of them, so that whichever is next polled can drive
+/// the future.
+#[derive(Default)]
+struct Wakers(Mutex<Vec<Waker>>);
+
+impl Wakers {
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
+
+impl Wake for Wakers {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let wakers = mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        for w in wakers {
+            w.wake();
+        }
+    }
+}
+
+impl<S, F, Fut, T> AsyncIou<S, F, Fut, T> {
+    /// Create a new [AsyncIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init` and awaiting the result.
+    pub const fn new(init: S, f: F) -> Self {
+        AsyncIou {
+            value: OnceLock::new(),
+            state: Mutex::new(AsyncIouState::PreInit(init, f)),
+            wakers: OnceLock::new(),
+        }
+    }
+
+    /// Create a new [AsyncIou] that is already initialized
+    /// with the value `t`. Its initialization function will
+    /// never be called.
+    pub fn ready(t: T) -> Self {
+        AsyncIou {
+            value: OnceLock::from(t),
+            state: Mutex::new(AsyncIouState::Init),
+            wakers: OnceLock::new(),
+        }
+    }
+
+    /// Check whether the value has been initialized yet.
+    pub fn is_init(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    fn wakers(&self) -> &Arc<Wakers> {
+        self.wakers.get_or_init(Arc::default)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AsyncIouState<S, F, Fut>> {
+        // Panics are caught and recorded in the state, so a
+        // poisoned mutex carries no extra information.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<S, F, Fut, T> AsyncIou<S, F, Fut, T>
+    where F: FnOnce(S) -> Fut, Fut: Future<Output = T>
+{
+    /// Initialize the [AsyncIou] if not yet initialized,
+    /// then return a reference to the initialized value.
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub async fn get(&self) -> &T {
+        future::poll_fn(|cx| self.poll_get(cx)).await
+    }
+
+    /// Initialize the [AsyncIou] if needed and return the
+    /// initialized value, consuming the [AsyncIou].
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub async fn unwrap(self) -> T {
+        self.get().await;
+        self.value.into_inner().expect("AsyncIou: corrupted cell")
+    }
+
+    fn poll_get(&self, cx: &mut Context<'_>) -> Poll<&T> {
+        if let Some(t) = self.value.get() {
+            return Poll::Ready(t);
+        }
+        self.wakers().register(cx.waker());
+        let mut state = self.lock();
+        let step = panic::catch_unwind(AssertUnwindSafe(|| {
+            match mem::replace(&mut *state, AsyncIouState::Init) {
+                AsyncIouState::PreInit(s, f) => *state = AsyncIouState::Running(Box::pin(f(s))),
+                other => *state = other,
+            }
+            match &mut *state {
+                AsyncIouState::Running(fut) => {
+                    let waker = Waker::from(Arc::clone(self.wakers()));
+                    fut.as_mut().poll(&mut Context::from_waker(&waker))
+                }
+                AsyncIouState::Poisoned(p) => p.panic("AsyncIou"),
+                _ => Poll::Pending,
+            }
+        }));
+        match step {
+            Ok(Poll::Ready(t)) => {
+                *state = AsyncIouState::Init;
+                // The value may only be set here, while the state
+                // is locked.
+                let _ = self.value.set(t);
+                drop(state);
+                self.wakers().wake_by_ref();
+                Poll::Ready(self.value.get().expect("AsyncIou: corrupted cell"))
+            }
+            Ok(Poll::Pending) => {
+                // Another awaiter may have finished
+                // initialization before we took the lock.
+                match self.value.get() {
+                    Some(t) => Poll::Ready(t),
+                    None => Poll::Pending,
+                }
+            }
+            Err(payload) => {
+                if !matches!(*state, AsyncIouState::Poisoned(_)) {
+                    *state = AsyncIouState::Poisoned(Poison::from_payload(&*payload));
+                }
+                drop(state);
+                self.wakers().wake_by_ref();
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+}
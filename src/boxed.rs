@@ -0,0 +1,73 @@
+//! Initialize-on-use with type-erased initialization functions.
+
+use alloc::boxed::Box;
+use core::cell::RefCell;
+
+use crate::lazy::{self, Lazy};
+use crate::{Iou, IouState};
+
+/// An [Iou] whose initialization function is boxed, so that
+/// its type does not depend on the type of the function.
+pub type BoxIou<S, T> = Iou<S, Box<dyn FnOnce(S) -> T>, T>;
+
+/// Boxed initialization function of a [DynIou].
+pub type DynInit<T> = Box<dyn FnOnce() -> T>;
+
+/// A [Lazy] whose initialization function is a boxed
+/// closure. Its type depends only on the type of its value.
+pub type DynIou<T> = Lazy<T, DynInit<T>>;
+
+impl<S, T> BoxIou<S, T> {
+    /// Create a new [BoxIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init`.
+    pub fn new_boxed(init: S, f: impl FnOnce(S) -> T + 'static) -> Self {
+        Iou::new(init, Box::new(f))
+    }
+}
+
+impl<T> DynIou<T> {
+    /// Create a new [DynIou] that will be initialized on
+    /// first use by calling `f`.
+    pub fn new_dyn(f: impl FnOnce() -> T + 'static) -> Self {
+        Lazy::from_fn(Box::new(f))
+    }
+}
+
+impl<S, F, T> Iou<S, F, T>
+    where F: FnOnce(S) -> T
+{
+    /// Convert into a [BoxIou] in the same state, boxing the
+    /// initialization function if the [Iou] is not yet
+    /// initialized.
+    pub fn into_boxed(self) -> BoxIou<S, T>
+        where F: 'static
+    {
+        Iou(RefCell::new(match self.0.into_inner() {
+            IouState::PreInit(s, f) => IouState::PreInit(s, Box::new(f)),
+            IouState::Init(t) => IouState::Init(t),
+            IouState::Poisoned(p) => IouState::Poisoned(p),
+            IouState::Empty => IouState::Empty,
+            IouState::Initializing => unreachable!(),
+        }))
+    }
+
+    /// Convert into a [DynIou] in the same state, boxing the
+    /// initialization function together with the
+    /// initialization data if the [Iou] is not yet
+    /// initialized.
+    pub fn into_dyn(self) -> DynIou<T>
+        where S: 'static, F: 'static, T: 'static
+    {
+        Iou(RefCell::new(match self.0.into_inner() {
+            IouState::PreInit(s, f) => {
+                let f: DynInit<T> = Box::new(move || f(s));
+                IouState::PreInit(f, lazy::call as fn(DynInit<T>) -> T)
+            }
+            IouState::Init(t) => IouState::Init(t),
+            IouState::Poisoned(p) => IouState::Poisoned(p),
+            IouState::Empty => IouState::Empty,
+            IouState::Initializing => unreachable!(),
+        }))
+    }
+}
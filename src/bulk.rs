@@ -0,0 +1,106 @@
+//! Parallel initialization of many initialize-on-use values.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{CancelIou, CancelToken, Poison, SyncIou};
+
+/// A thread-safe cell that can be initialized by
+/// [init_all].
+pub trait ForceInit: Sync {
+    /// Check whether the cell has been initialized yet.
+    fn is_init(&self) -> bool;
+
+    /// Initialize the cell if not yet initialized. Returns
+    /// whether this call ran the initialization function,
+    /// rather than finding the cell already initialized.
+    fn force_init(&self) -> bool;
+}
+
+impl<S, F, T> ForceInit for SyncIou<S, F, T>
+    where F: FnOnce(S) -> T, Self: Sync
+{
+    fn is_init(&self) -> bool {
+        SyncIou::is_init(self)
+    }
+
+    fn force_init(&self) -> bool {
+        self.init_here()
+    }
+}
+
+impl<S, F, T> ForceInit for CancelIou<S, F, T>
+    where F: FnMut(S, &CancelToken) -> Result<T, S>, Self: Sync
+{
+    fn is_init(&self) -> bool {
+        CancelIou::is_init(self)
+    }
+
+    fn force_init(&self) -> bool {
+        self.init_here()
+    }
+}
+
+/// Outcome of [init_all].
+#[derive(Debug, Default, Clone)]
+pub struct InitReport {
+    /// Number of cells whose initialization function was
+    /// run by [init_all].
+    pub initialized: usize,
+    /// Number of cells found already initialized, possibly
+    /// by another thread while [init_all] ran, and so left
+    /// untouched.
+    pub skipped: usize,
+    /// Cells whose initialization panicked, by index in the
+    /// slice given to [init_all], in index order.
+    pub failures: Vec<(usize, Poison)>,
+}
+
+impl InitReport {
+    /// Check whether every cell is now initialized.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Initialize every cell of `cells` that is not yet
+/// initialized, using at most `threads` threads (and at
+/// least one). Cells are handed out to the threads in
+/// order, one at a time.
+///
+/// A panic while initializing a cell is recorded in the
+/// returned [InitReport], and does not stop the other cells
+/// from being initialized.
+pub fn init_all<C: ForceInit + ?Sized>(cells: &[&C], threads: usize) -> InitReport {
+    let next = AtomicUsize::new(0);
+    let work = || {
+        let mut report = InitReport::default();
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let Some(cell) = cells.get(i) else {
+                return report;
+            };
+            let forced = panic::catch_unwind(AssertUnwindSafe(|| cell.force_init()));
+            match forced {
+                Ok(true) => report.initialized += 1,
+                Ok(false) => report.skipped += 1,
+                Err(payload) => report.failures.push((i, Poison::from_payload(&*payload))),
+            }
+        }
+    };
+    let mut report = InitReport::default();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, cells.len().max(1)))
+            .map(|_| scope.spawn(work))
+            .collect();
+        for worker in workers {
+            let r = worker.join().expect("init_all: corrupted worker");
+            report.initialized += r.initialized;
+            report.skipped += r.skipped;
+            report.failures.extend(r.failures);
+        }
+    });
+    report.failures.sort_by_key(|&(i, _)| i);
+    report
+}
@@ -0,0 +1,285 @@
+//! Initialize-on-use with timeouts and cancellation.
+
+use std::error;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::{catch_poison, Poison};
+
+/// Initialize on use, cancellably: a thread-safe value that
+/// will be lazily initialized at first reference by a
+/// function that can be asked to give up.
+///
+/// The initialization function is given the initialization
+/// data and a [CancelToken]. It should check
+/// [CancelToken::is_cancelled] from time to time, and once
+/// it is set, give the initialization data back by returning
+/// `Err`. The [CancelIou] is then uninitialized again, with
+/// its initialization data and function, and the next
+/// access starts a new initialization. The initialization
+/// function is only ever run by one thread at a time.
+///
+/// Cancellation is cooperative: [CancelIou::cancel] and the
+/// deadline of [CancelIou::borrow_timeout] only set the
+/// token. A thread waiting for another thread's
+/// initialization stops waiting at its own deadline, but
+/// the thread running the initialization function returns
+/// only when that function does. If an initialization gives
+/// up at its deadline, a thread waiting for it starts a new
+/// initialization with its own deadline; if it is cancelled,
+/// the waiting threads are told so. Reentrant use by the
+/// initialization function deadlocks until the deadline.
+///
+/// If the initialization function panics, the panic is
+/// propagated and the [CancelIou] is poisoned.
+pub struct CancelIou<S, F, T> {
+    value: OnceLock<T>,
+    state: Mutex<CancelIouState<S, F>>,
+    done: Condvar,
+}
+
+enum CancelIouState<S, F> {
+    /// Not yet initialized.
+    PreInit(S, F),
+    /// Initialization function is running.
+    Running(Arc<CancelToken>),
+    /// Initialized.
+    Init,
+    /// Initialization panicked.
+    Poisoned(Poison),
+}
+
+/// Request to the initialization function of a [CancelIou]
+/// to give up.
+#[derive(Debug)]
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    /// Check whether the initialization function should give
+    /// up, because the initialization was cancelled or its
+    /// deadline has passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire) || self.is_expired()
+    }
+
+    /// The time by which the initialization should finish,
+    /// if it has a deadline.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// Reasons a [CancelIou] access can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelError {
+    /// Initialization did not finish by the deadline.
+    TimedOut,
+    /// Initialization was cancelled, or the initialization
+    /// function gave up.
+    Cancelled,
+    /// The initialization function panicked.
+    Poisoned,
+}
+
+impl fmt::Display for CancelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CancelError::TimedOut => write!(f, "CancelIou: initialization timed out"),
+            CancelError::Cancelled => write!(f, "CancelIou: initialization cancelled"),
+            CancelError::Poisoned => write!(f, "CancelIou: poisoned cell"),
+        }
+    }
+}
+
+impl error::Error for CancelError {}
+
+impl<S, F, T> CancelIou<S, F, T> {
+    /// Create a new [CancelIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init` and a [CancelToken].
+    pub const fn new(init: S, f: F) -> Self {
+        CancelIou {
+            value: OnceLock::new(),
+            state: Mutex::new(CancelIouState::PreInit(init, f)),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Create a new [CancelIou] that is already initialized
+    /// with the value `t`. Its initialization function will
+    /// never be called.
+    pub fn ready(t: T) -> Self {
+        CancelIou {
+            value: OnceLock::from(t),
+            state: Mutex::new(CancelIouState::Init),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Check whether the value has been initialized yet.
+    pub fn is_init(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Ask the running initialization function, if any, to
+    /// give up. Threads waiting for that initialization
+    /// return [CancelError::Cancelled]. Returns whether an
+    /// initialization was running.
+    pub fn cancel(&self) -> bool {
+        match &*self.lock() {
+            CancelIouState::Running(token) => {
+                token.cancelled.store(true, Ordering::Release);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CancelIouState<S, F>> {
+        // Panics are caught and recorded in the state, so a
+        // poisoned mutex carries no extra information.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Panic reporting the failed access `e`.
+    fn fail(&self, e: CancelError) -> ! {
+        match &*self.lock() {
+            CancelIouState::Poisoned(p) => p.panic("CancelIou"),
+            _ => panic!("{}", e),
+        }
+    }
+}
+
+impl<S, F, T> CancelIou<S, F, T>
+    where F: FnMut(S, &CancelToken) -> Result<T, S>
+{
+    /// Initialize the [CancelIou] if not yet initialized,
+    /// then return a reference to the initialized value.
+    /// Waits for as long as initialization takes.
+    ///
+    /// # Panics
+    /// Panics if initialization is cancelled, or on poisoned
+    /// cell.
+    #[allow(clippy::should_implement_trait)]
+    pub fn borrow(&self) -> &T {
+        match self.try_borrow() {
+            Ok(t) => t,
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Initialize the [CancelIou] if not yet initialized,
+    /// then return a reference to the initialized value.
+    /// Waits for as long as initialization takes. Returns
+    /// an error if initialization is cancelled, or if the
+    /// [CancelIou] is poisoned.
+    pub fn try_borrow(&self) -> Result<&T, CancelError> {
+        self.borrow_until(None)
+    }
+
+    /// Initialize the [CancelIou] if not yet initialized,
+    /// then return a reference to the initialized value.
+    /// Returns [CancelError::TimedOut] if initialization
+    /// does not finish within `timeout`, leaving the
+    /// [CancelIou] uninitialized, and otherwise fails as
+    /// [CancelIou::try_borrow] does.
+    pub fn borrow_timeout(&self, timeout: Duration) -> Result<&T, CancelError> {
+        self.borrow_until(Instant::now().checked_add(timeout))
+    }
+
+    /// As [CancelIou::borrow], waiting for as long as
+    /// initialization takes, but returning whether this call
+    /// ran the initialization function.
+    pub(crate) fn init_here(&self) -> bool {
+        match self.init_until(None) {
+            Ok(ran) => ran,
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn borrow_until(&self, deadline: Option<Instant>) -> Result<&T, CancelError> {
+        self.init_until(deadline)?;
+        Ok(self.value.get().expect("CancelIou: corrupted cell"))
+    }
+
+    /// Initialize the [CancelIou] if not yet initialized,
+    /// giving up at `deadline`. Returns whether this call ran
+    /// the initialization function to completion.
+    fn init_until(&self, deadline: Option<Instant>) -> Result<bool, CancelError> {
+        if self.is_init() {
+            return Ok(false);
+        }
+        let mut state = self.lock();
+        loop {
+            let running = match &*state {
+                CancelIouState::Init => return Ok(false),
+                CancelIouState::Poisoned(_) => return Err(CancelError::Poisoned),
+                CancelIouState::PreInit(..) => break,
+                CancelIouState::Running(token) => Arc::clone(token),
+            };
+            state = match deadline {
+                None => self.done.wait(state).unwrap_or_else(|e| e.into_inner()),
+                Some(d) => {
+                    let now = Instant::now();
+                    if now >= d {
+                        return Err(CancelError::TimedOut);
+                    }
+                    self.done.wait_timeout(state, d - now).unwrap_or_else(|e| e.into_inner()).0
+                }
+            };
+            // A cancelled initialization is cancelled for its
+            // waiters too. One that gave up at its own
+            // deadline is taken over by the next loop.
+            if running.cancelled.load(Ordering::Acquire)
+                && matches!(*state, CancelIouState::PreInit(..))
+            {
+                return Err(CancelError::Cancelled);
+            }
+        }
+        let token = Arc::new(CancelToken { cancelled: AtomicBool::new(false), deadline });
+        let running = CancelIouState::Running(Arc::clone(&token));
+        let (s, mut f) = match mem::replace(&mut *state, running) {
+            CancelIouState::PreInit(s, f) => (s, f),
+            _ => unreachable!(),
+        };
+        drop(state);
+        let r = catch_poison(
+            || f(s, &token),
+            |p| {
+                *self.lock() = CancelIouState::Poisoned(p);
+                self.done.notify_all();
+            },
+        );
+        let mut state = self.lock();
+        let r = match r {
+            Ok(t) => {
+                // The value may only be set here, while the state
+                // is locked.
+                let _ = self.value.set(t);
+                *state = CancelIouState::Init;
+                Ok(true)
+            }
+            Err(s) => {
+                *state = CancelIouState::PreInit(s, f);
+                if token.is_expired() && !token.cancelled.load(Ordering::Acquire) {
+                    Err(CancelError::TimedOut)
+                } else {
+                    Err(CancelError::Cancelled)
+                }
+            }
+        };
+        drop(state);
+        self.done.notify_all();
+        r
+    }
+}
@@ -0,0 +1,44 @@
+//! Lazy composition of initialize-on-use values.
+
+use crate::Iou;
+
+impl<S, F, T> Iou<S, F, T>
+    where F: FnOnce(S) -> T
+{
+    /// Create a new [Iou] that will be initialized on first
+    /// use by initializing this [Iou] and applying `g` to its
+    /// value. Nothing is initialized until the new [Iou] is.
+    ///
+    /// This [Iou] becomes the initialization data of the new
+    /// one, and so remains available through [Iou::seed]
+    /// until then.
+    pub fn map<U, G>(self, g: G) -> Iou<Self, impl FnOnce(Self) -> U, U>
+        where G: FnOnce(T) -> U
+    {
+        Iou::new(self, move |iou: Self| g(iou.unwrap()))
+    }
+
+    /// Create a new [Iou] that will be initialized on first
+    /// use by initializing this [Iou], applying `g` to its
+    /// value, and initializing the [Iou] returned by `g`.
+    /// Nothing is initialized until the new [Iou] is.
+    pub fn and_then<S2, F2, U, G>(self, g: G) -> Iou<Self, impl FnOnce(Self) -> U, U>
+        where G: FnOnce(T) -> Iou<S2, F2, U>, F2: FnOnce(S2) -> U
+    {
+        Iou::new(self, move |iou: Self| g(iou.unwrap()).unwrap())
+    }
+
+    /// Create a new [Iou] that will be initialized on first
+    /// use by initializing this [Iou] and `other`, in that
+    /// order, and pairing their values. Nothing is
+    /// initialized until the new [Iou] is.
+    #[allow(clippy::type_complexity)]
+    pub fn zip<S2, F2, U>(
+        self,
+        other: Iou<S2, F2, U>,
+    ) -> Iou<(Self, Iou<S2, F2, U>), impl FnOnce((Self, Iou<S2, F2, U>)) -> (T, U), (T, U)>
+        where F2: FnOnce(S2) -> U
+    {
+        Iou::new((self, other), |(a, b): (Self, Iou<S2, F2, U>)| (a.unwrap(), b.unwrap()))
+    }
+}
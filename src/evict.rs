@@ -0,0 +1,260 @@
+//! Initialize-on-use with budgeted eviction.
+
+use alloc::rc::{Rc, Weak};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell, Ref, RefMut};
+use core::mem;
+
+use crate::RecomputeIou;
+
+/// Approximate memory footprint of a value, used by an
+/// [EvictRegistry] to keep its cells within budget.
+pub trait SizeHint {
+    /// Approximate number of bytes used by the value,
+    /// including memory it owns.
+    fn size_hint(&self) -> usize;
+}
+
+impl<T> SizeHint for Vec<T> {
+    fn size_hint(&self) -> usize {
+        mem::size_of::<Self>() + self.capacity() * mem::size_of::<T>()
+    }
+}
+
+impl SizeHint for String {
+    fn size_hint(&self) -> usize {
+        mem::size_of::<Self>() + self.capacity()
+    }
+}
+
+/// Registry of [EvictIou] cells that keeps the total size of
+/// their values within a byte budget.
+///
+/// Whenever a registered cell is initialized, cells are
+/// evicted in least-recently-borrowed order until the total
+/// size of initialized values, as given by [SizeHint] at
+/// initialization time, is within budget. The cell just
+/// initialized and cells that are currently borrowed are
+/// never evicted, so the budget may be exceeded when they
+/// alone exceed it.
+pub struct EvictRegistry(Rc<Registry>);
+
+struct Registry {
+    budget: usize,
+    cells: RefCell<Vec<Weak<dyn Evict>>>,
+    clock: Cell<u64>,
+}
+
+/// Operations an [EvictRegistry] needs on its cells.
+trait Evict {
+    /// Size of the value, or zero if not initialized.
+    fn size(&self) -> usize;
+    /// Time of last borrow.
+    fn last_used(&self) -> u64;
+    /// Evict the value if it is not borrowed.
+    fn try_evict(&self) -> bool;
+}
+
+/// Initialize on use, evictably: a [RecomputeIou] registered
+/// with an [EvictRegistry], which may evict its value to
+/// stay within budget. An evicted value is recomputed on
+/// next use.
+///
+/// Create an [EvictIou] with [EvictRegistry::register].
+pub struct EvictIou<S, F, T> {
+    iou: RecomputeIou<S, F, T>,
+    registry: Rc<Registry>,
+    size: Cell<usize>,
+    last_used: Cell<u64>,
+}
+
+impl EvictRegistry {
+    /// Create a new [EvictRegistry] that keeps its cells'
+    /// values within `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        EvictRegistry(Rc::new(Registry {
+            budget,
+            cells: RefCell::new(Vec::new()),
+            clock: Cell::new(0),
+        }))
+    }
+
+    /// Create a new [EvictIou] registered with this
+    /// [EvictRegistry], that will be initialized on first
+    /// use by applying the function `f` to the
+    /// initialization data `init`.
+    pub fn register<S, F, T>(&self, init: S, f: F) -> Rc<EvictIou<S, F, T>>
+        where S: 'static, F: Fn(&S) -> T + 'static, T: SizeHint + 'static
+    {
+        let cell = Rc::new(EvictIou {
+            iou: RecomputeIou::new(init, f),
+            registry: Rc::clone(&self.0),
+            size: Cell::new(0),
+            last_used: Cell::new(0),
+        });
+        let weak: Weak<dyn Evict> = Rc::downgrade(&cell) as Weak<EvictIou<S, F, T>>;
+        self.0.cells.borrow_mut().push(weak);
+        cell
+    }
+
+    /// The budget in bytes.
+    pub fn budget(&self) -> usize {
+        self.0.budget
+    }
+
+    /// Total size of the initialized values of the
+    /// registered cells.
+    pub fn total_size(&self) -> usize {
+        self.0.total_size()
+    }
+
+    /// Evict least-recently-borrowed values until the total
+    /// size is within budget, or until no unborrowed values
+    /// remain.
+    pub fn trim(&self) {
+        self.0.trim(None);
+    }
+}
+
+impl Registry {
+    fn tick(&self) -> u64 {
+        let t = self.clock.get() + 1;
+        self.clock.set(t);
+        t
+    }
+
+    fn total_size(&self) -> usize {
+        self.cells
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|c| c.size())
+            .sum()
+    }
+
+    /// Evict values until within budget, sparing the cell
+    /// at `keep`.
+    fn trim(&self, keep: Option<*const ()>) {
+        let mut cells = self.cells.borrow_mut();
+        cells.retain(|c| c.strong_count() > 0);
+        let mut live: Vec<Rc<dyn Evict>> = cells
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|c| c.size() > 0)
+            .collect();
+        drop(cells);
+        let mut total: usize = live.iter().map(|c| c.size()).sum();
+        live.sort_by_key(|c| c.last_used());
+        for c in live {
+            if total <= self.budget {
+                break;
+            }
+            if Some(Rc::as_ptr(&c) as *const ()) == keep {
+                continue;
+            }
+            let size = c.size();
+            if c.try_evict() {
+                total -= size;
+            }
+        }
+    }
+}
+
+impl<S, F, T> Evict for EvictIou<S, F, T> {
+    fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    fn last_used(&self) -> u64 {
+        self.last_used.get()
+    }
+
+    fn try_evict(&self) -> bool {
+        if self.iou.is_borrowed() {
+            return false;
+        }
+        self.evict();
+        true
+    }
+}
+
+impl<S, F, T> EvictIou<S, F, T> {
+    /// Check whether the value has been initialized yet.
+    pub fn is_init(&self) -> bool {
+        self.iou.is_init()
+    }
+
+    /// Approximate size of the value, as given by
+    /// [SizeHint] when it was initialized, or zero if the
+    /// [EvictIou] is not initialized.
+    pub fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    /// Return a reference to the initialization data.
+    ///
+    /// # Panics
+    /// Panics if the initialization data is mutably borrowed.
+    pub fn seed(&self) -> Ref<'_, S> {
+        self.iou.seed()
+    }
+
+    /// Evict the value, returning it if the [EvictIou] was
+    /// initialized. The value will be recomputed on next
+    /// use.
+    ///
+    /// # Panics
+    /// Panics if the value is borrowed.
+    pub fn evict(&self) -> Option<T> {
+        self.size.set(0);
+        self.iou.evict()
+    }
+}
+
+impl<S, F, T> EvictIou<S, F, T>
+    where F: Fn(&S) -> T, T: SizeHint
+{
+    /// Initialize the [EvictIou] if not yet initialized,
+    /// evicting other cells of its registry as needed to
+    /// stay within budget.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function.
+    pub fn init(&self) {
+        self.last_used.set(self.registry.tick());
+        if self.iou.is_init() {
+            return;
+        }
+        self.iou.init();
+        let size = self.iou.peek().map_or(0, |t| t.size_hint());
+        self.size.set(size);
+        self.registry.trim(Some(self as *const Self as *const ()));
+    }
+
+    /// Initialize the [EvictIou] if not yet initialized,
+    /// then return a reference to the initialized value.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function, or if the value is mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.init();
+        self.iou.borrow()
+    }
+
+    /// Initialize the [EvictIou] if not yet initialized,
+    /// then return a mutable reference to the initialized
+    /// value. Changes made through the reference are lost
+    /// if the value is evicted, and are not reflected in
+    /// the value's recorded size.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function, or if the value is borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.init();
+        self.iou.borrow_mut()
+    }
+}
@@ -0,0 +1,177 @@
+//! Initialize-on-use with expiring values.
+
+use std::cell::{Cell, Ref, RefMut};
+use std::time::{Duration, Instant};
+
+use crate::RecomputeIou;
+
+/// Source of the current time for an [ExpiringIou].
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> Instant;
+}
+
+/// [Clock] reading the system's monotonic clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Initialize on use, with expiry: a value that will be
+/// lazily initialized at first reference, and lazily
+/// reinitialized at the first reference after a given
+/// time-to-live has passed since it was initialized.
+///
+/// An [ExpiringIou] is a [RecomputeIou] whose value is
+/// evicted when it is found to have expired. Time is read
+/// from a [Clock], by default the [SystemClock]; supply
+/// another with [ExpiringIou::with_clock], for example to
+/// control time in tests.
+pub struct ExpiringIou<S, F, T, C = SystemClock> {
+    iou: RecomputeIou<S, F, T>,
+    ttl: Duration,
+    clock: C,
+    initialized_at: Cell<Option<Instant>>,
+}
+
+impl<S, F, T> ExpiringIou<S, F, T> {
+    /// Create a new [ExpiringIou] that will be initialized
+    /// on first use by applying the function `f` to the
+    /// initialization data `init`, and reinitialized the
+    /// same way on first use after the value is `ttl` old.
+    pub const fn new(init: S, f: F, ttl: Duration) -> Self {
+        Self::with_clock(init, f, ttl, SystemClock)
+    }
+
+    /// Create a new [ExpiringIou] that is already
+    /// initialized with the value `t`, which expires `ttl`
+    /// from now. It will be reinitialized on first use after
+    /// that by applying the function `f` to the
+    /// initialization data `init`.
+    pub fn ready(init: S, f: F, ttl: Duration, t: T) -> Self {
+        Self::ready_with_clock(init, f, ttl, SystemClock, t)
+    }
+}
+
+impl<S, F, T, C> ExpiringIou<S, F, T, C>
+    where C: Clock
+{
+    /// Create a new [ExpiringIou] as with
+    /// [ExpiringIou::ready], reading time from `clock`.
+    pub fn ready_with_clock(init: S, f: F, ttl: Duration, clock: C, t: T) -> Self {
+        let now = clock.now();
+        ExpiringIou {
+            iou: RecomputeIou::ready(init, f, t),
+            ttl,
+            clock,
+            initialized_at: Cell::new(Some(now)),
+        }
+    }
+}
+
+impl<S, F, T, C> ExpiringIou<S, F, T, C> {
+    /// Create a new [ExpiringIou] as with
+    /// [ExpiringIou::new], reading time from `clock`.
+    pub const fn with_clock(init: S, f: F, ttl: Duration, clock: C) -> Self {
+        ExpiringIou {
+            iou: RecomputeIou::new(init, f),
+            ttl,
+            clock,
+            initialized_at: Cell::new(None),
+        }
+    }
+
+    /// Return a reference to the initialization data.
+    ///
+    /// # Panics
+    /// Panics if the initialization data is mutably borrowed.
+    pub fn seed(&self) -> Ref<'_, S> {
+        self.iou.seed()
+    }
+
+    /// Evict the value, returning it if the [ExpiringIou]
+    /// was initialized, expired or not.
+    ///
+    /// # Panics
+    /// Panics if the value is borrowed.
+    pub fn evict(&self) -> Option<T> {
+        self.initialized_at.set(None);
+        self.iou.evict()
+    }
+}
+
+impl<S, F, T, C> ExpiringIou<S, F, T, C>
+    where C: Clock
+{
+    /// Check whether the value is initialized and has not
+    /// expired.
+    pub fn is_fresh(&self) -> bool {
+        match self.initialized_at.get() {
+            Some(t) => self.clock.now().saturating_duration_since(t) < self.ttl,
+            None => false,
+        }
+    }
+
+    /// Return a reference to the value if it is initialized
+    /// and has not expired, without initializing it.
+    ///
+    /// # Panics
+    /// Panics if the value is mutably borrowed.
+    pub fn peek_if_fresh(&self) -> Option<Ref<'_, T>> {
+        if self.is_fresh() {
+            self.iou.peek()
+        } else {
+            None
+        }
+    }
+}
+
+impl<S, F, T, C> ExpiringIou<S, F, T, C>
+    where F: Fn(&S) -> T, C: Clock
+{
+    /// Initialize the [ExpiringIou] if it is not
+    /// initialized or its value has expired.
+    ///
+    /// # Panics
+    /// Panics if the value has expired but is still
+    /// borrowed, or on reentrant use by the initialization
+    /// function.
+    pub fn init(&self) {
+        if self.is_fresh() {
+            return;
+        }
+        self.evict();
+        self.iou.init();
+        self.initialized_at.set(Some(self.clock.now()));
+    }
+
+    /// Initialize the [ExpiringIou] if it is not
+    /// initialized or its value has expired, then return a
+    /// reference to the value.
+    ///
+    /// # Panics
+    /// Panics if the value has expired but is still
+    /// mutably borrowed, or on reentrant use by the
+    /// initialization function.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.init();
+        self.iou.borrow()
+    }
+
+    /// Initialize the [ExpiringIou] if it is not
+    /// initialized or its value has expired, then return a
+    /// mutable reference to the value. Changes made through
+    /// the reference are lost when the value expires.
+    ///
+    /// # Panics
+    /// Panics if the value is borrowed, or on reentrant use
+    /// by the initialization function.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.init();
+        self.iou.borrow_mut()
+    }
+}
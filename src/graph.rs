@@ -0,0 +1,187 @@
+//! Initialize-on-use values that depend on each other.
+
+use std::cell::{RefCell, Ref};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+use crate::{catch_poison, Poison};
+
+/// A set of lazily initialized values, identified by keys,
+/// whose initialization functions may use each other's
+/// values.
+///
+/// Each node's initialization function is given the
+/// [LazyGraph], and requests the values it depends on with
+/// [LazyGraph::get]. A node is initialized at most once, on
+/// first request. A dependency cycle is reported as a
+/// [GraphError::Cycle] giving the path of keys around the
+/// cycle, rather than as a panic.
+///
+/// The error returned by an initialization function, or the
+/// poison of a panic in it, is recorded in its node and
+/// returned by every later request for that node.
+pub struct LazyGraph<K, V> {
+    nodes: HashMap<K, RefCell<Node<K, V>>>,
+    stack: RefCell<Vec<K>>,
+}
+
+/// Initialization function of a [LazyGraph] node.
+type NodeInit<K, V> = Box<dyn FnOnce(&LazyGraph<K, V>) -> Result<V, GraphError<K>>>;
+
+enum Node<K, V> {
+    /// Not yet initialized.
+    Pending(NodeInit<K, V>),
+    /// Initialization function is running.
+    Forcing,
+    /// Initialized.
+    Done(V),
+    /// Initialization failed.
+    Failed(GraphError<K>),
+}
+
+/// Reasons a [LazyGraph] node cannot be initialized.
+#[derive(Debug, Clone)]
+pub enum GraphError<K> {
+    /// The node depends on itself. The path starts and ends
+    /// with the first node requested again, and lists the
+    /// nodes in the order they were requested.
+    Cycle(Vec<K>),
+    /// The node, or one it depends on, does not exist.
+    Missing(K),
+    /// The initialization function of the node, or of one it
+    /// depends on, panicked.
+    Poisoned(Poison),
+}
+
+impl<K: fmt::Debug> fmt::Display for GraphError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Cycle(path) => {
+                write!(f, "LazyGraph: dependency cycle: ")?;
+                for (i, k) in path.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    write!(f, "{:?}", k)?;
+                }
+                Ok(())
+            }
+            GraphError::Missing(k) => write!(f, "LazyGraph: missing node {:?}", k),
+            GraphError::Poisoned(p) => match p.message() {
+                Some(m) => write!(f, "LazyGraph: poisoned node: {}", m),
+                None => write!(f, "LazyGraph: poisoned node"),
+            },
+        }
+    }
+}
+
+impl<K: fmt::Debug> error::Error for GraphError<K> {}
+
+impl<K, V> LazyGraph<K, V> {
+    /// Create a new, empty [LazyGraph].
+    pub fn new() -> Self {
+        LazyGraph {
+            nodes: HashMap::new(),
+            stack: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl<K, V> Default for LazyGraph<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> LazyGraph<K, V>
+    where K: Eq + Hash + Clone
+{
+    /// Add a node with key `k`, that will be initialized on
+    /// first request by applying `f` to the [LazyGraph].
+    /// Replaces any existing node with the same key.
+    pub fn insert<F>(&mut self, k: K, f: F)
+        where F: FnOnce(&Self) -> Result<V, GraphError<K>> + 'static
+    {
+        self.nodes.insert(k, RefCell::new(Node::Pending(Box::new(f))));
+    }
+
+    /// Check whether the node with key `k` exists and has
+    /// been successfully initialized.
+    pub fn is_forced(&self, k: &K) -> bool {
+        match self.nodes.get(k) {
+            Some(node) => matches!(*node.borrow(), Node::Done(_)),
+            None => false,
+        }
+    }
+
+    /// The keys of the nodes that have been successfully
+    /// initialized, in no particular order.
+    pub fn forced(&self) -> impl Iterator<Item = &K> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| matches!(*node.borrow(), Node::Done(_)))
+            .map(|(k, _)| k)
+    }
+
+    /// Initialize the node with key `k` if not yet
+    /// initialized, then return a reference to its value, or
+    /// the error that prevented its initialization.
+    ///
+    /// # Panics
+    /// Panics if the initialization function of the node
+    /// panics, after poisoning the node.
+    pub fn get(&self, k: &K) -> Result<Ref<'_, V>, GraphError<K>> {
+        let node = self.nodes.get(k).ok_or_else(|| GraphError::Missing(k.clone()))?;
+        if let Ok(n) = Ref::filter_map(node.borrow(), |n| match n {
+            Node::Done(v) => Some(v),
+            _ => None,
+        }) {
+            return Ok(n);
+        }
+        let f = match &mut *node.borrow_mut() {
+            Node::Done(_) => None,
+            Node::Failed(e) => return Err(e.clone()),
+            Node::Forcing => {
+                let stack = self.stack.borrow();
+                let start = stack.iter().position(|s| s == k).expect("LazyGraph: corrupted graph");
+                let mut path = stack[start..].to_vec();
+                path.push(k.clone());
+                return Err(GraphError::Cycle(path));
+            }
+            pending => match mem::replace(pending, Node::Forcing) {
+                Node::Pending(f) => Some(f),
+                _ => unreachable!(),
+            },
+        };
+        if let Some(f) = f {
+            self.stack.borrow_mut().push(k.clone());
+            let r = catch_poison(
+                || f(self),
+                |p| {
+                    self.stack.borrow_mut().pop();
+                    *node.borrow_mut() = Node::Failed(GraphError::Poisoned(p));
+                },
+            );
+            self.stack.borrow_mut().pop();
+            match r {
+                Ok(v) => *node.borrow_mut() = Node::Done(v),
+                Err(e) => {
+                    *node.borrow_mut() = Node::Failed(e.clone());
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Ref::map(
+            node.borrow(),
+            |n| {
+                match n {
+                    Node::Done(v) => v,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,27 @@
+//! Initialize-on-use without initialization data.
+
+use crate::Iou;
+
+/// An [Iou] whose initialization function takes no
+/// initialization data.
+///
+/// The initialization function is kept as the
+/// initialization data of the [Iou], and is called by a
+/// plain function pointer, so a [Lazy] is an ordinary [Iou]
+/// in every other respect. The default type of the function
+/// is a function pointer, so that a [Lazy] initialized by a
+/// named function can be written `Lazy<T>`.
+pub type Lazy<T, F = fn() -> T> = Iou<F, fn(F) -> T, T>;
+
+/// Initialization function of a [Lazy].
+pub(crate) fn call<F: FnOnce() -> T, T>(f: F) -> T {
+    f()
+}
+
+impl<T, F: FnOnce() -> T> Lazy<T, F> {
+    /// Create a new [Lazy] that will be initialized on first
+    /// use by calling `f`.
+    pub const fn from_fn(f: F) -> Self {
+        Iou::new(f, call::<F, T> as fn(F) -> T)
+    }
+}
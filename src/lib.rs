@@ -7,29 +7,491 @@
 //! expensive or time consuming, and the price is better
 //! paid later.
 //!
-//! An [Iou] will have a "corrupted cell" if its initialization
-//! function panics during initialization. Operations on an [Iou]
-//! with a corrupted cell will themselves panic.
+//! An [Iou] will be "poisoned" if its initialization
+//! function panics during initialization. Most operations
+//! on a poisoned [Iou] will themselves panic; use
+//! [Iou::is_poisoned] and [Iou::try_borrow] to inspect a
+//! poisoned [Iou] without panicking, and [Iou::reset_with]
+//! to recover it. The message of the panic that poisoned an
+//! [Iou] is kept, and is available from
+//! `Iou::poison_message`.
+//!
+//! An initialization function that uses the [Iou] it is
+//! initializing, directly or through other lazily
+//! initialized values, is an error: such "reentrant" uses
+//! are reported as [IouError::Reentrant] by
+//! [Iou::try_borrow], and cause a panic naming the [Iou]'s
+//! value type from [Iou::borrow].
+//!
+//! An [Iou] need not stay initialized: [Iou::reset_with]
+//! re-arms it with new initialization data, discarding any
+//! value, so that the value is recomputed on next use, and
+//! [Iou::take] moves the value out, leaving the [Iou] empty.
+//! An [Iou] not yet initialized can be given new
+//! initialization data, keeping its initialization
+//! function, with [Iou::reset].
+//!
+//! [Iou] implements `Debug`, `Clone`, `Default` and
+//! `From<T>` without forcing initialization, so that it
+//! can be stored in structs that derive them.
+//! `PartialEq`, `Eq` and `Hash` compare and hash values,
+//! and so do force initialization.
+//!
+//! When the price is better paid concurrently,
+//! `Iou::spawn_init` starts initialization on another
+//! thread, or with any `Spawn`, straight away. The returned
+//! `Prefetched` waits for the result only if it is used
+//! before the result is ready.
+//!
+//! [Lazy] is an [Iou] whose initialization function needs
+//! no initialization data, created by [Iou::from_fn].
+//!
+//! [Iou::map], [Iou::and_then] and [Iou::zip] compose
+//! [Iou]s into new ones without initializing them: the
+//! composed [Iou]s are initialized only when the result is.
+//!
+//! The type of an [Iou] includes the type of its
+//! initialization function, which for a closure cannot be
+//! named. `BoxIou` and `DynIou` box the initialization
+//! function to remove it from the type, so that [Iou]s
+//! built from different closures can be stored together.
+//! Any [Iou] can be converted to either.
+//!
+//! [RecomputeIou] keeps its initialization data and a
+//! reusable initialization function, so that its value can
+//! be evicted and recomputed at will. `ExpiringIou` builds
+//! on it to recompute values that are older than a given
+//! time-to-live. `EvictIou` cells share an `EvictRegistry`,
+//! which evicts least-recently-used values to keep the
+//! total size of its cells' values within a budget.
+//!
+//! `SyncIou` is a thread-safe counterpart to [Iou]: it may
+//! be shared between threads, and guarantees that its
+//! initialization function is run exactly once even when
+//! several threads race to first use it. `CancelIou` is a
+//! thread-safe variant whose initialization function is
+//! given a `CancelToken`, so that a hung initialization can
+//! be timed out by `CancelIou::borrow_timeout` or stopped
+//! by `CancelIou::cancel`. `init_all` initializes a batch
+//! of `SyncIou`s, `CancelIou`s or other `ForceInit` cells
+//! in parallel on a bounded number of threads.
+//!
+//! [TryIou] is a variant of [Iou] whose initialization
+//! function may fail. A failed initialization is recorded,
+//! and the error is returned by later accesses rather than
+//! corrupting the cell. [RetryIou] goes further, keeping
+//! its initialization data after a failure so that
+//! initialization can be retried on a later access.
+//!
+//! `AsyncIou` is initialized by awaiting a future returned
+//! by its initialization function, and can be shared by any
+//! number of concurrent awaiters.
+//!
+//! `LazyGraph` holds lazily initialized values, identified
+//! by keys, whose initialization functions request each
+//! other's values. Dependency cycles are reported with the
+//! path around the cycle rather than causing a panic.
+//!
+//! `IouMap` lazily initializes the value for each key on
+//! first lookup, by applying one initialization function to
+//! the key, and `SyncIouMap` is its thread-safe
+//! counterpart. Values can be invalidated, to be recomputed
+//! on next lookup.
+//!
+//! `IouVec` is a fixed-length vector whose elements are
+//! lazily initialized, each on first use, by applying one
+//! initialization function to the element's index.
+//!
+//! # Features
+//!
+//! The crate is `no_std` unless its default `std` feature
+//! is enabled. [Iou], [Lazy], [TryIou], [RetryIou] and
+//! [RecomputeIou] are always available, and need no
+//! allocator. Without `std`, a poisoned [Iou] does not
+//! record the message of the panic that poisoned it, and
+//! `SyncIou`, `CancelIou`, `AsyncIou`, `ExpiringIou`,
+//! `LazyGraph`, `IouMap`, `SyncIouMap`, `Prefetched` and
+//! `init_all` are unavailable. The `alloc` feature,
+//! implied by `std`, makes available the types that need
+//! an allocator but not `std`: `BoxIou`, `DynIou`,
+//! `EvictIou` and `IouVec`.
+//!
+//! The `no_std` builds are checked in CI by building for a
+//! bare-metal target, which has no `std` to fall back on:
+//!
+//! ```text
+//! cargo build --no-default-features --target thumbv7em-none-eabihf
+//! cargo build --no-default-features --features alloc --target thumbv7em-none-eabihf
+//! cargo test --no-default-features
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod async_iou;
+#[cfg(feature = "alloc")]
+mod boxed;
+#[cfg(feature = "std")]
+mod bulk;
+#[cfg(feature = "std")]
+mod cancel;
+mod combine;
+#[cfg(feature = "alloc")]
+mod evict;
+#[cfg(feature = "std")]
+mod expiring;
+#[cfg(feature = "std")]
+mod graph;
+mod lazy;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+mod prefetch;
+mod recompute;
+mod retry;
+#[cfg(feature = "std")]
+mod sync;
+mod try_iou;
+#[cfg(feature = "alloc")]
+mod vec;
+
+#[cfg(feature = "std")]
+pub use async_iou::AsyncIou;
+#[cfg(feature = "alloc")]
+pub use boxed::{BoxIou, DynInit, DynIou};
+#[cfg(feature = "std")]
+pub use bulk::{init_all, ForceInit, InitReport};
+#[cfg(feature = "std")]
+pub use cancel::{CancelError, CancelIou, CancelToken};
+#[cfg(feature = "alloc")]
+pub use evict::{EvictIou, EvictRegistry, SizeHint};
+#[cfg(feature = "std")]
+pub use expiring::{Clock, ExpiringIou, SystemClock};
+#[cfg(feature = "std")]
+pub use graph::{GraphError, LazyGraph};
+pub use lazy::Lazy;
+#[cfg(feature = "std")]
+pub use map::{IouMap, IouMapRef, SyncIouMap, SyncIouMapRef};
+#[cfg(feature = "std")]
+pub use prefetch::{Prefetched, Spawn, ThreadSpawner};
+pub use recompute::RecomputeIou;
+pub use retry::{RetryIou, RetryPolicy};
+#[cfg(feature = "std")]
+pub use sync::{SyncIou, SyncIouRef, SyncIouRefMut};
+pub use try_iou::TryIou;
+#[cfg(feature = "alloc")]
+pub use vec::IouVec;
 
-use std::cell::{RefCell, Ref, RefMut};
+use core::any;
+use core::cell::{RefCell, Ref, RefMut};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
 
 /// Initialize on use: a value that will be lazily
 /// initialized at first reference.
+///
+/// Besides being uninitialized or initialized, an [Iou] may
+/// be poisoned, by a panic in its initialization function,
+/// or empty, after its value is moved out by [Iou::take].
+/// Accessors that need the value panic on a poisoned or
+/// empty [Iou], or return [IouError::Poisoned] or
+/// [IouError::Empty], until it is re-armed with
+/// [Iou::reset_with].
 pub struct Iou<S, F, T>(RefCell<IouState<S, F, T>>);
 
-enum IouState<S, F, T> {
+pub(crate) enum IouState<S, F, T> {
     /// Not yet initialized.
-    PreInit(Option<(S, F)>),
+    PreInit(S, F),
+    /// Initialization function is running.
+    Initializing,
     /// Initialized.
     Init(T),
+    /// Initialization panicked.
+    Poisoned(Poison),
+    /// Value has been taken, and the cell not re-armed.
+    Empty,
+}
+
+/// Contents of an [Iou], as returned by [Iou::into_state].
+#[derive(Debug)]
+pub enum IouContents<S, F, T> {
+    /// Not yet initialized: the initialization data and
+    /// function.
+    PreInit(S, F),
+    /// Initialized.
+    Init(T),
+    /// Initialization panicked.
+    Poisoned(Poison),
+    /// Value has been taken, and the [Iou] not re-armed.
+    Empty,
+}
+
+/// Record of the panic that poisoned a cell.
+#[derive(Debug, Default, Clone)]
+pub struct Poison {
+    #[cfg(feature = "std")]
+    message: Option<String>,
+}
+
+impl Poison {
+    /// Record the panic with the given payload, keeping its
+    /// message if it has one.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_payload(payload: &(dyn any::Any + Send)) -> Self {
+        let message = if let Some(m) = payload.downcast_ref::<&str>() {
+            Some(m.to_string())
+        } else {
+            payload.downcast_ref::<String>().cloned()
+        };
+        Poison { message }
+    }
+
+    /// The message of the panic, if it had a string
+    /// message. Always `None` without the `std` feature.
+    pub fn message(&self) -> Option<&str> {
+        #[cfg(feature = "std")]
+        return self.message.as_deref();
+        #[cfg(not(feature = "std"))]
+        return None;
+    }
+
+    /// Panic reporting this poison for the named cell type.
+    pub(crate) fn panic(&self, name: &str) -> ! {
+        match self.message() {
+            Some(m) => panic!("{}: poisoned cell: {}", name, m),
+            None => panic!("{}: poisoned cell", name),
+        }
+    }
+}
+
+/// Run `f`. If it panics, pass a record of the panic to
+/// `poison`, then resume the panic.
+pub(crate) fn catch_poison<R>(f: impl FnOnce() -> R, poison: impl FnOnce(Poison)) -> R {
+    #[cfg(feature = "std")]
+    {
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(r) => r,
+            Err(payload) => {
+                poison(Poison::from_payload(&*payload));
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        // Without `std` the panic cannot be caught, so poison
+        // the cell while unwinding past this guard instead.
+        struct Guard<P: FnOnce(Poison)>(Option<P>);
+
+        impl<P: FnOnce(Poison)> Drop for Guard<P> {
+            fn drop(&mut self) {
+                if let Some(poison) = self.0.take() {
+                    poison(Poison::default());
+                }
+            }
+        }
+
+        let mut guard = Guard(Some(poison));
+        let r = f();
+        guard.0 = None;
+        r
+    }
+}
+
+/// Reasons an [Iou] access can fail without panicking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IouError {
+    /// The initialization function panicked.
+    Poisoned,
+    /// The [Iou] is already borrowed in a way that
+    /// conflicts with the requested access.
+    AlreadyBorrowed,
+    /// The [Iou] was accessed by its own initialization
+    /// function.
+    Reentrant,
+    /// The value was taken out of the [Iou] by [Iou::take],
+    /// and the [Iou] has not since been re-armed.
+    Empty,
 }
 
+impl fmt::Display for IouError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IouError::Poisoned => write!(f, "Iou: poisoned cell"),
+            IouError::AlreadyBorrowed => write!(f, "Iou: already borrowed"),
+            IouError::Reentrant => write!(f, "Iou: reentrant initialization"),
+            IouError::Empty => write!(f, "Iou: empty cell"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IouError {}
+
 impl<S, F, T> Iou<S, F, T> {
     /// Create a new [Iou] that will be initialized on first
     /// use by applying the function `f` to the
     /// initialization data `init`.
-    pub fn new(init: S, f: F) -> Self {
-        Iou(RefCell::new(IouState::PreInit(Some((init, f)))))
+    pub const fn new(init: S, f: F) -> Self {
+        Iou(RefCell::new(IouState::PreInit(init, f)))
+    }
+
+    /// Create a new [Iou] that is already initialized with
+    /// the value `t`. Its initialization function will never
+    /// be called.
+    pub const fn ready(t: T) -> Self {
+        Iou(RefCell::new(IouState::Init(t)))
+    }
+
+    /// Check whether the value has been initialized yet. An
+    /// [Iou] whose initialization function is running is not
+    /// yet initialized.
+    pub fn is_init(&self) -> bool {
+        matches!(&*self.0.borrow(), IouState::Init(_))
+    }
+
+    /// Check whether the initialization function panicked
+    /// during initialization.
+    pub fn is_poisoned(&self) -> bool {
+        matches!(&*self.0.borrow(), IouState::Poisoned(_))
+    }
+
+    /// If the [Iou] is poisoned and the panic that poisoned
+    /// it had a string message, return a copy of that
+    /// message.
+    #[cfg(feature = "std")]
+    pub fn poison_message(&self) -> Option<String> {
+        match &*self.0.borrow() {
+            IouState::Poisoned(p) => p.message().map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Re-arm the [Iou] to be initialized on next use by
+    /// applying the function `f` to the initialization data
+    /// `init`, discarding any initialized value. This is the
+    /// way to recompute the value of an [Iou] from new
+    /// data, to refill an [Iou] emptied by [Iou::take], and
+    /// to recover a poisoned [Iou].
+    ///
+    /// The initialization function of an [Iou] is consumed
+    /// by initialization, so a function must be supplied
+    /// here even if it is the same as the original.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is currently borrowed or being
+    /// initialized.
+    pub fn reset_with(&self, init: S, f: F) {
+        let mut iou = self.0.borrow_mut();
+        if let IouState::Initializing = *iou {
+            panic!("Iou: reset during initialization");
+        }
+        *iou = IouState::PreInit(init, f);
+    }
+
+    /// Replace the initialization data of an [Iou] that is
+    /// not yet initialized with `init`, keeping its
+    /// initialization function, so that the value will be
+    /// computed from `init` on first use.
+    ///
+    /// An initialization function is consumed by
+    /// initialization, so an [Iou] that is initialized,
+    /// poisoned or empty has none to keep: `init` is then
+    /// returned as an error and the [Iou] left unchanged.
+    /// Use [Iou::reset_with] to re-arm it with a new
+    /// function.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is currently borrowed or being
+    /// initialized.
+    pub fn reset(&self, init: S) -> Result<(), S> {
+        match &mut *self.0.borrow_mut() {
+            IouState::PreInit(s, _) => {
+                *s = init;
+                Ok(())
+            }
+            IouState::Initializing => panic!("Iou: reset during initialization"),
+            _ => Err(init),
+        }
+    }
+
+    /// If the [Iou] is initialized, take its value, leaving
+    /// the [Iou] empty. Its initialization function has been
+    /// consumed, so an empty [Iou] cannot recompute its
+    /// value: like a poisoned [Iou], it makes [Iou::borrow],
+    /// [Iou::init], [Iou::unwrap] and the other accessors
+    /// that need the value panic, and [Iou::try_borrow]
+    /// return [IouError::Empty], until it is re-armed with
+    /// [Iou::reset_with]. Returns `None`, leaving the [Iou]
+    /// unchanged, if the [Iou] is not initialized.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is currently borrowed.
+    pub fn take(&self) -> Option<T> {
+        let mut iou = self.0.borrow_mut();
+        match core::mem::replace(&mut *iou, IouState::Empty) {
+            IouState::Init(t) => Some(t),
+            state => {
+                *iou = state;
+                None
+            }
+        }
+    }
+
+    /// Return a reference to the value if the [Iou] is
+    /// initialized, without initializing it.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is mutably borrowed.
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(
+            self.0.borrow(),
+            |s| {
+                match s {
+                    IouState::Init(t) => Some(t),
+                    _ => None,
+                }
+            },
+        ).ok()
+    }
+
+    /// Return a mutable reference to the value if the [Iou]
+    /// is initialized, without initializing it.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is borrowed.
+    pub fn peek_mut(&self) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(
+            self.0.borrow_mut(),
+            |s| {
+                match s {
+                    IouState::Init(t) => Some(t),
+                    _ => None,
+                }
+            },
+        ).ok()
+    }
+
+    /// Return a reference to the initialization data if the
+    /// [Iou] is not yet initialized.
+    ///
+    /// # Panics
+    /// Panics if the [Iou] is mutably borrowed.
+    pub fn seed(&self) -> Option<Ref<'_, S>> {
+        Ref::filter_map(
+            self.0.borrow(),
+            |s| {
+                match s {
+                    IouState::PreInit(s, _) => Some(s),
+                    _ => None,
+                }
+            },
+        ).ok()
     }
 }
 
@@ -40,47 +502,102 @@ impl<S, F, T> Iou<S, F, T>
     /// initialized value, consuming the [Iou].
     ///
     /// # Panics
-    /// Panics on corrupted cell.
+    /// Panics on poisoned or empty cell.
     pub fn unwrap(self) -> T {
         match self.0.into_inner() {
-            IouState::PreInit(Some((s, f))) => f(s),
+            IouState::PreInit(s, f) => f(s),
             IouState::Init(t) => t,
-            _ => panic!("Iou: corrupted cell"),
+            IouState::Poisoned(p) => p.panic("Iou"),
+            IouState::Empty => panic!("{}", IouError::Empty),
+            IouState::Initializing => unreachable!(),
         }
     }
 
-    /// Check whether the value has been initialized yet.
+    /// Consume the [Iou], returning its initialization data
+    /// and function if it is not yet initialized, or its
+    /// value if it is. The initialization function is not
+    /// run.
     ///
     /// # Panics
-    /// Panics on corrupted cell.
-    pub fn is_init(&self) -> bool {
-        match &*self.0.borrow() {
-            IouState::Init(_) => true,
-            IouState::PreInit(Some(_)) => false,
-            _ => panic!("Iou: corrupted cell"),
+    /// Panics on poisoned or empty cell.
+    pub fn into_seed(self) -> Result<(S, F), T> {
+        match self.into_state() {
+            IouContents::PreInit(s, f) => Ok((s, f)),
+            IouContents::Init(t) => Err(t),
+            IouContents::Poisoned(p) => p.panic("Iou"),
+            IouContents::Empty => panic!("{}", IouError::Empty),
+        }
+    }
+
+    /// Consume the [Iou], returning its contents. The
+    /// initialization function is not run.
+    pub fn into_state(self) -> IouContents<S, F, T> {
+        match self.0.into_inner() {
+            IouState::PreInit(s, f) => IouContents::PreInit(s, f),
+            IouState::Init(t) => IouContents::Init(t),
+            IouState::Poisoned(p) => IouContents::Poisoned(p),
+            IouState::Empty => IouContents::Empty,
+            IouState::Initializing => unreachable!(),
         }
     }
 
     /// Initialize the [Iou] if not yet initialized.
     ///
+    /// If the initialization function panics, the [Iou] is
+    /// poisoned with the panic's message and the panic is
+    /// then resumed.
+    ///
     /// # Panics
-    /// Panics on corrupted cell.
+    /// Panics on poisoned or empty cell, on reentrant use by
+    /// the initialization function, or if the [Iou] is
+    /// mutably borrowed.
     pub fn init(&self) {
-        if self.is_init() {
-            return;
+        match self.try_init() {
+            Ok(()) => (),
+            Err(IouError::Poisoned) => match &*self.0.borrow() {
+                IouState::Poisoned(p) => p.panic("Iou"),
+                _ => unreachable!(),
+            },
+            Err(IouError::Reentrant) => panic!(
+                "Iou<_, _, {}>: reentrant initialization: \
+                 the initialization function used the Iou it was initializing",
+                any::type_name::<T>(),
+            ),
+            Err(e) => panic!("{}", e),
         }
-        let mut iou = self.0.borrow_mut();
-        if let IouState::PreInit(p) = &mut *iou {
-            let (s, f) = p.take().expect("Iou: corrupted cell");
-            *iou = IouState::Init(f(s));
+    }
+
+    /// Initialize the [Iou] if not yet initialized,
+    /// returning an error rather than panicking if the [Iou]
+    /// is poisoned or empty, is borrowed, or is being
+    /// initialized.
+    fn try_init(&self) -> Result<(), IouError> {
+        match &*self.0.try_borrow().map_err(|_| IouError::AlreadyBorrowed)? {
+            IouState::Init(_) => return Ok(()),
+            IouState::Poisoned(_) => return Err(IouError::Poisoned),
+            IouState::Initializing => return Err(IouError::Reentrant),
+            IouState::Empty => return Err(IouError::Empty),
+            IouState::PreInit(..) => (),
         }
+        let mut iou = self.0.try_borrow_mut().map_err(|_| IouError::AlreadyBorrowed)?;
+        let (s, f) = match core::mem::replace(&mut *iou, IouState::Initializing) {
+            IouState::PreInit(s, f) => (s, f),
+            _ => unreachable!(),
+        };
+        // Release the cell while `f` runs, so that reentrant
+        // uses find it initializing rather than borrowed.
+        drop(iou);
+        let t = catch_poison(|| f(s), |p| *self.0.borrow_mut() = IouState::Poisoned(p));
+        *self.0.borrow_mut() = IouState::Init(t);
+        Ok(())
     }
 
     /// Initialize the [Iou] if not yet initialized, then
     /// return a reference to the initialized value.
     ///
     /// # Panics
-    /// Panics on corrupted cell.
+    /// Panics on poisoned or empty cell, or on reentrant use
+    /// by the initialization function.
     pub fn borrow(&self) -> Ref<'_, T> {
         self.init();
         Ref::map(
@@ -88,7 +605,7 @@ impl<S, F, T> Iou<S, F, T>
             |s| {
                 match s {
                     IouState::Init(t) => t,
-                    _ => panic!("Iou: corrupted cell"),
+                    _ => panic!("Iou: poisoned cell"),
                 }
             },
         )
@@ -98,7 +615,8 @@ impl<S, F, T> Iou<S, F, T>
     /// return a mutable reference to the initialized value.
     ///
     /// # Panics
-    /// Panics on corrupted cell.
+    /// Panics on poisoned or empty cell, or on reentrant use
+    /// by the initialization function.
     pub fn borrow_mut(&self) -> RefMut<'_, T> {
         self.init();
         RefMut::map(
@@ -106,9 +624,157 @@ impl<S, F, T> Iou<S, F, T>
             |s| {
                 match s {
                     IouState::Init(t) => t,
-                    _ => panic!("Iou: corrupted cell"),
+                    _ => panic!("Iou: poisoned cell"),
                 }
             },
         )
     }
+
+    /// Initialize the [Iou] if not yet initialized, then
+    /// return a mutable reference to the initialized value.
+    /// Since the [Iou] is exclusively borrowed, no borrow
+    /// tracking is needed for the returned reference.
+    ///
+    /// # Panics
+    /// Panics on poisoned or empty cell.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.init();
+        match self.0.get_mut() {
+            IouState::Init(t) => t,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Initialize the [Iou] if not yet initialized, then
+    /// return a reference to the initialized value. Returns
+    /// an error rather than panicking if the [Iou] is
+    /// poisoned or empty, is mutably borrowed, or is being
+    /// initialized.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, IouError> {
+        self.try_init()?;
+        let iou = self.0.try_borrow().map_err(|_| IouError::AlreadyBorrowed)?;
+        Ok(Ref::map(
+            iou,
+            |s| {
+                match s {
+                    IouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+
+    /// Initialize the [Iou] if not yet initialized, then
+    /// return a mutable reference to the initialized value.
+    /// Returns an error rather than panicking if the [Iou] is
+    /// poisoned or empty, is borrowed, or is being
+    /// initialized.
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, IouError> {
+        self.try_init()?;
+        let iou = self.0.try_borrow_mut().map_err(|_| IouError::AlreadyBorrowed)?;
+        Ok(RefMut::map(
+            iou,
+            |s| {
+                match s {
+                    IouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+}
+
+/// Formats the value of an initialized [Iou], and a
+/// placeholder such as `Iou(<uninit>)` otherwise. Never
+/// initializes the [Iou].
+impl<S, F, T: fmt::Debug> fmt::Debug for Iou<S, F, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Placeholder(&'static str);
+
+        impl fmt::Debug for Placeholder {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        let mut d = f.debug_tuple("Iou");
+        match self.0.try_borrow() {
+            Ok(iou) => match &*iou {
+                IouState::Init(t) => d.field(t),
+                IouState::PreInit(..) => d.field(&Placeholder("<uninit>")),
+                IouState::Initializing => d.field(&Placeholder("<initializing>")),
+                IouState::Poisoned(_) => d.field(&Placeholder("<poisoned>")),
+                IouState::Empty => d.field(&Placeholder("<empty>")),
+            },
+            Err(_) => d.field(&Placeholder("<borrowed>")),
+        };
+        d.finish()
+    }
+}
+
+/// Clones the [Iou] in whatever state it is in: an
+/// uninitialized [Iou] clones its initialization data and
+/// function, and an initialized one its value. Never
+/// initializes the [Iou].
+///
+/// # Panics
+/// Panics if the [Iou] is mutably borrowed or being
+/// initialized.
+impl<S: Clone, F: Clone, T: Clone> Clone for Iou<S, F, T> {
+    fn clone(&self) -> Self {
+        let state = match &*self.0.borrow() {
+            IouState::PreInit(s, f) => IouState::PreInit(s.clone(), f.clone()),
+            IouState::Init(t) => IouState::Init(t.clone()),
+            IouState::Poisoned(p) => IouState::Poisoned(p.clone()),
+            IouState::Empty => IouState::Empty,
+            IouState::Initializing => panic!("Iou: clone during initialization"),
+        };
+        Iou(RefCell::new(state))
+    }
+}
+
+/// Creates an already-initialized [Iou]. Its initialization
+/// function will never be called.
+impl<S, F, T> From<T> for Iou<S, F, T> {
+    fn from(t: T) -> Self {
+        Iou::ready(t)
+    }
+}
+
+/// Creates an [Iou] already initialized with the default
+/// value. Its initialization function will never be called.
+impl<S, F, T: Default> Default for Iou<S, F, T> {
+    fn default() -> Self {
+        Iou::from(T::default())
+    }
+}
+
+/// Compares the values of two [Iou]s, initializing both if
+/// needed.
+///
+/// # Panics
+/// Panics under the same conditions as [Iou::borrow].
+impl<S, F, T> PartialEq for Iou<S, F, T>
+    where F: FnOnce(S) -> T, T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow() == *other.borrow()
+    }
+}
+
+impl<S, F, T> Eq for Iou<S, F, T>
+    where F: FnOnce(S) -> T, T: Eq
+{}
+
+/// Hashes the value of the [Iou], initializing it if
+/// needed, so that an [Iou] hashes the same as its value.
+///
+/// # Panics
+/// Panics under the same conditions as [Iou::borrow].
+impl<S, F, T> Hash for Iou<S, F, T>
+    where F: FnOnce(S) -> T, T: Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.borrow().hash(state);
+    }
 }
@@ -0,0 +1,188 @@
+//! Initialize-on-use values for every key of a map.
+
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+/// Initialize on use, by key: a map whose value for each key
+/// is lazily initialized at first lookup of that key, by
+/// applying a single initialization function to the key.
+///
+/// The map is only borrowed briefly by each operation, so
+/// the initialization function may look up other keys of
+/// the same [IouMap]. A panic in the initialization function
+/// leaves the entry uninitialized, to be retried on next
+/// lookup.
+pub struct IouMap<K, V, F> {
+    entries: RefCell<HashMap<K, Rc<OnceCell<V>>>>,
+    f: F,
+}
+
+/// Reference to a value of an [IouMap]. The value stays
+/// available through the reference even if its entry is
+/// invalidated.
+pub struct IouMapRef<V>(Rc<OnceCell<V>>);
+
+impl<V> Deref for IouMapRef<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.0.get().expect("IouMap: corrupted cell")
+    }
+}
+
+impl<K, V, F> IouMap<K, V, F> {
+    /// Create a new, empty [IouMap] whose values will be
+    /// initialized on first lookup by applying the function
+    /// `f` to their keys.
+    pub fn new(f: F) -> Self {
+        IouMap {
+            entries: RefCell::new(HashMap::new()),
+            f,
+        }
+    }
+}
+
+impl<K, V, F> IouMap<K, V, F>
+    where K: Eq + Hash + Clone, F: Fn(&K) -> V
+{
+    /// Initialize the value for key `k` if not yet
+    /// initialized, then return a reference to it.
+    ///
+    /// # Panics
+    /// Panics on reentrant use of the same key by the
+    /// initialization function.
+    pub fn get(&self, k: &K) -> IouMapRef<V> {
+        let cell = Rc::clone(self.entries.borrow_mut().entry(k.clone()).or_default());
+        cell.get_or_init(|| (self.f)(k));
+        IouMapRef(cell)
+    }
+
+    /// Return a reference to the value for key `k` if it is
+    /// initialized, without initializing it.
+    pub fn get_if_init(&self, k: &K) -> Option<IouMapRef<V>> {
+        let cell = Rc::clone(self.entries.borrow().get(k)?);
+        cell.get()?;
+        Some(IouMapRef(cell))
+    }
+
+    /// Check whether the value for key `k` has been
+    /// initialized yet.
+    pub fn is_init(&self, k: &K) -> bool {
+        self.entries.borrow().get(k).is_some_and(|cell| cell.get().is_some())
+    }
+
+    /// Discard the value for key `k`, so that it is
+    /// recomputed on next lookup. Returns whether the value
+    /// was initialized.
+    pub fn invalidate(&self, k: &K) -> bool {
+        self.entries.borrow_mut().remove(k).is_some_and(|cell| cell.get().is_some())
+    }
+
+    /// The initialized entries of the [IouMap], in no
+    /// particular order. Values initialized during the
+    /// iteration are not included.
+    pub fn iter_init(&self) -> impl Iterator<Item = (K, IouMapRef<V>)> {
+        let entries: Vec<_> = self
+            .entries
+            .borrow()
+            .iter()
+            .filter(|(_, cell)| cell.get().is_some())
+            .map(|(k, cell)| (k.clone(), IouMapRef(Rc::clone(cell))))
+            .collect();
+        entries.into_iter()
+    }
+}
+
+/// Initialize on use, by key, across threads: a thread-safe
+/// counterpart to [IouMap].
+///
+/// Each value is initialized exactly once even when several
+/// threads race to first look up its key: the other threads
+/// wait for the initialization to finish. Initialization of
+/// different keys proceeds in parallel. Reentrant use of
+/// the same key by the initialization function deadlocks.
+pub struct SyncIouMap<K, V, F> {
+    entries: Mutex<HashMap<K, Arc<OnceLock<V>>>>,
+    f: F,
+}
+
+/// Reference to a value of a [SyncIouMap]. The value stays
+/// available through the reference even if its entry is
+/// invalidated.
+pub struct SyncIouMapRef<V>(Arc<OnceLock<V>>);
+
+impl<V> Deref for SyncIouMapRef<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.0.get().expect("SyncIouMap: corrupted cell")
+    }
+}
+
+impl<K, V, F> SyncIouMap<K, V, F> {
+    /// Create a new, empty [SyncIouMap] whose values will be
+    /// initialized on first lookup by applying the function
+    /// `f` to their keys.
+    pub fn new(f: F) -> Self {
+        SyncIouMap {
+            entries: Mutex::new(HashMap::new()),
+            f,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, Arc<OnceLock<V>>>> {
+        // The map is never left inconsistent by a panic, so a
+        // poisoned mutex carries no information.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K, V, F> SyncIouMap<K, V, F>
+    where K: Eq + Hash + Clone, F: Fn(&K) -> V
+{
+    /// Initialize the value for key `k` if not yet
+    /// initialized, then return a reference to it.
+    pub fn get(&self, k: &K) -> SyncIouMapRef<V> {
+        let cell = Arc::clone(self.lock().entry(k.clone()).or_default());
+        cell.get_or_init(|| (self.f)(k));
+        SyncIouMapRef(cell)
+    }
+
+    /// Return a reference to the value for key `k` if it is
+    /// initialized, without initializing it.
+    pub fn get_if_init(&self, k: &K) -> Option<SyncIouMapRef<V>> {
+        let cell = Arc::clone(self.lock().get(k)?);
+        cell.get()?;
+        Some(SyncIouMapRef(cell))
+    }
+
+    /// Check whether the value for key `k` has been
+    /// initialized yet.
+    pub fn is_init(&self, k: &K) -> bool {
+        self.lock().get(k).is_some_and(|cell| cell.get().is_some())
+    }
+
+    /// Discard the value for key `k`, so that it is
+    /// recomputed on next lookup. Returns whether the value
+    /// was initialized.
+    pub fn invalidate(&self, k: &K) -> bool {
+        self.lock().remove(k).is_some_and(|cell| cell.get().is_some())
+    }
+
+    /// The initialized entries of the [SyncIouMap], in no
+    /// particular order. Values initialized during the
+    /// iteration are not included.
+    pub fn iter_init(&self) -> impl Iterator<Item = (K, SyncIouMapRef<V>)> {
+        let entries: Vec<_> = self
+            .lock()
+            .iter()
+            .filter(|(_, cell)| cell.get().is_some())
+            .map(|(k, cell)| (k.clone(), SyncIouMapRef(Arc::clone(cell))))
+            .collect();
+        entries.into_iter()
+    }
+}
@@ -0,0 +1,74 @@
+//! Initialize-on-use with initialization started in the
+//! background.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::Iou;
+
+/// An [Iou] whose value is being computed in the
+/// background, as returned by [Iou::spawn_init]. Its
+/// initialization waits for the background computation to
+/// finish.
+pub type Prefetched<T> = Iou<Receiver<thread::Result<T>>, fn(Receiver<thread::Result<T>>) -> T, T>;
+
+/// A way to run a job in the background.
+pub trait Spawn {
+    /// Run `job` in the background. If the job is dropped
+    /// without being run, the [Prefetched] waiting for it
+    /// is poisoned on first use.
+    fn spawn(&self, job: Box<dyn FnOnce() + Send>);
+}
+
+/// A [Spawn] that runs each job on a new thread.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadSpawner;
+
+impl Spawn for ThreadSpawner {
+    fn spawn(&self, job: Box<dyn FnOnce() + Send>) {
+        thread::spawn(job);
+    }
+}
+
+/// Initialization function of a [Prefetched]: wait for the
+/// background computation, and resume its panic if it
+/// panicked, so that the [Prefetched] is poisoned with the
+/// same message.
+fn join<T>(rx: Receiver<thread::Result<T>>) -> T {
+    match rx.recv() {
+        Ok(Ok(t)) => t,
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+        Err(_) => panic!("Prefetched: background initialization was dropped"),
+    }
+}
+
+impl<S, F, T> Iou<S, F, T>
+    where F: FnOnce(S) -> T
+{
+    /// Start applying the function `f` to the
+    /// initialization data `init` on a new thread, and
+    /// return a [Prefetched] that will be initialized with
+    /// the result. Use of the [Prefetched] waits for the
+    /// computation to finish if it has not yet.
+    ///
+    /// If `f` panics, the [Prefetched] is poisoned on first
+    /// use, with the message of the panic.
+    pub fn spawn_init(init: S, f: F) -> Prefetched<T>
+        where S: Send + 'static, F: Send + 'static, T: Send + 'static
+    {
+        Self::spawn_init_with(&ThreadSpawner, init, f)
+    }
+
+    /// As [Iou::spawn_init], but run `f` with `spawner`
+    /// rather than on a new thread.
+    pub fn spawn_init_with(spawner: &impl Spawn, init: S, f: F) -> Prefetched<T>
+        where S: Send + 'static, F: Send + 'static, T: Send + 'static
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        spawner.spawn(Box::new(move || {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(|| f(init))));
+        }));
+        Iou::new(rx, join)
+    }
+}
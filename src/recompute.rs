@@ -0,0 +1,179 @@
+//! Initialize-on-use with a reusable initializer.
+
+use core::any;
+use core::cell::{Cell, RefCell, Ref, RefMut};
+
+/// Initialize on use, recomputably: a value that will be
+/// lazily initialized at first reference, and that keeps
+/// its initialization data and function so that it can be
+/// evicted and recomputed.
+///
+/// Unlike an [Iou](crate::Iou), a [RecomputeIou] applies
+/// its initialization function to a reference to its
+/// initialization data, which remains available through
+/// [RecomputeIou::seed]. Changing the initialization data
+/// through [RecomputeIou::seed_mut] evicts the value, so
+/// that it is recomputed from the new data on next use.
+///
+/// Since nothing is consumed by initialization, a panic in
+/// the initialization function leaves the [RecomputeIou]
+/// uninitialized rather than poisoned.
+pub struct RecomputeIou<S, F, T> {
+    init: RefCell<S>,
+    f: F,
+    value: RefCell<Option<T>>,
+    initializing: Cell<bool>,
+}
+
+impl<S, F, T> RecomputeIou<S, F, T> {
+    /// Create a new [RecomputeIou] that will be initialized
+    /// on first use by applying the function `f` to the
+    /// initialization data `init`.
+    pub const fn new(init: S, f: F) -> Self {
+        RecomputeIou {
+            init: RefCell::new(init),
+            f,
+            value: RefCell::new(None),
+            initializing: Cell::new(false),
+        }
+    }
+
+    /// Create a new [RecomputeIou] that is already
+    /// initialized with the value `t`, and that will be
+    /// reinitialized after eviction by applying the function
+    /// `f` to the initialization data `init`.
+    pub const fn ready(init: S, f: F, t: T) -> Self {
+        RecomputeIou {
+            init: RefCell::new(init),
+            f,
+            value: RefCell::new(Some(t)),
+            initializing: Cell::new(false),
+        }
+    }
+
+    /// Check whether the value has been initialized yet.
+    pub fn is_init(&self) -> bool {
+        self.value.borrow().is_some()
+    }
+
+    /// Return a reference to the initialization data.
+    ///
+    /// # Panics
+    /// Panics if the initialization data is mutably borrowed.
+    pub fn seed(&self) -> Ref<'_, S> {
+        self.init.borrow()
+    }
+
+    /// Return a reference to the value if the [RecomputeIou]
+    /// is initialized, without initializing it.
+    ///
+    /// # Panics
+    /// Panics if the value is mutably borrowed.
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.value.borrow(), Option::as_ref).ok()
+    }
+
+    /// Evict the value, then return a mutable reference to
+    /// the initialization data. The value will be recomputed
+    /// from the changed data on next use.
+    ///
+    /// # Panics
+    /// Panics if the value or the initialization data is
+    /// borrowed.
+    pub fn seed_mut(&self) -> RefMut<'_, S> {
+        self.evict();
+        self.init.borrow_mut()
+    }
+
+    /// Evict the value, returning it if the [RecomputeIou]
+    /// was initialized. The value will be recomputed on
+    /// next use.
+    ///
+    /// # Panics
+    /// Panics if the value is borrowed.
+    pub fn evict(&self) -> Option<T> {
+        self.value.borrow_mut().take()
+    }
+
+    /// Check whether the value is currently borrowed.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn is_borrowed(&self) -> bool {
+        self.value.try_borrow_mut().is_err()
+    }
+
+    /// Consume the [RecomputeIou], returning its
+    /// initialization data and function.
+    pub fn into_parts(self) -> (S, F) {
+        (self.init.into_inner(), self.f)
+    }
+}
+
+impl<S, F, T> RecomputeIou<S, F, T>
+    where F: Fn(&S) -> T
+{
+    /// Initialize the [RecomputeIou] if needed and return
+    /// the initialized value, consuming the [RecomputeIou].
+    pub fn unwrap(self) -> T {
+        match self.value.into_inner() {
+            Some(t) => t,
+            None => (self.f)(&self.init.into_inner()),
+        }
+    }
+
+    /// Initialize the [RecomputeIou] if not yet
+    /// initialized.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function, or if the initialization data is mutably
+    /// borrowed.
+    pub fn init(&self) {
+        if self.is_init() {
+            return;
+        }
+        if self.initializing.replace(true) {
+            panic!(
+                "RecomputeIou<_, _, {}>: reentrant initialization: \
+                 the initialization function used the RecomputeIou it was initializing",
+                any::type_name::<T>(),
+            );
+        }
+        struct ClearOnDrop<'a>(&'a Cell<bool>);
+
+        impl Drop for ClearOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+
+        let initializing = ClearOnDrop(&self.initializing);
+        let t = (self.f)(&self.init.borrow());
+        drop(initializing);
+        *self.value.borrow_mut() = Some(t);
+    }
+
+    /// Initialize the [RecomputeIou] if not yet
+    /// initialized, then return a reference to the
+    /// initialized value.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function, or if the value is mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.init();
+        Ref::map(self.value.borrow(), |v| v.as_ref().expect("RecomputeIou: corrupted cell"))
+    }
+
+    /// Initialize the [RecomputeIou] if not yet
+    /// initialized, then return a mutable reference to the
+    /// initialized value. Changes made through the reference
+    /// are lost if the value is evicted.
+    ///
+    /// # Panics
+    /// Panics on reentrant use by the initialization
+    /// function, or if the value is borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.init();
+        RefMut::map(self.value.borrow_mut(), |v| v.as_mut().expect("RecomputeIou: corrupted cell"))
+    }
+}
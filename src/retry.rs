@@ -0,0 +1,181 @@
+//! Initialize-on-use with retried fallible initialization.
+
+use core::cell::{Cell, RefCell, Ref, RefMut};
+
+/// When a [RetryIou] should retry a failed initialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Retry on every access until initialization succeeds.
+    Always,
+    /// Give up after the given number of failed
+    /// attempts. A limit of zero behaves as a limit of one.
+    Attempts(usize),
+}
+
+/// Initialize on use, retrying failures: a value that will
+/// be lazily initialized at first reference by a function
+/// that may fail, and that keeps its initialization data
+/// when it does.
+///
+/// The initialization function is given mutable access to
+/// the initialization data rather than ownership of it, so
+/// a failed attempt leaves the [RetryIou] ready to try again
+/// on the next access. Once the [RetryPolicy] gives up, the
+/// last error is recorded and returned by every later
+/// access, as for a [TryIou](crate::TryIou).
+pub struct RetryIou<S, F, T, E> {
+    state: RefCell<RetryIouState<S, F, T, E>>,
+    policy: RetryPolicy,
+    attempts: Cell<usize>,
+}
+
+enum RetryIouState<S, F, T, E> {
+    /// Not yet initialized.
+    PreInit(S, F),
+    /// Initialized.
+    Init(T),
+    /// Initialization failed and will not be retried.
+    Failed(E),
+}
+
+impl<S, F, T, E> RetryIou<S, F, T, E> {
+    /// Create a new [RetryIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init`, retrying on every access
+    /// until `f` succeeds.
+    pub const fn new(init: S, f: F) -> Self {
+        Self::with_policy(init, f, RetryPolicy::Always)
+    }
+
+    /// Create a new [RetryIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init`, retrying failures
+    /// according to `policy`.
+    pub const fn with_policy(init: S, f: F, policy: RetryPolicy) -> Self {
+        RetryIou {
+            state: RefCell::new(RetryIouState::PreInit(init, f)),
+            policy,
+            attempts: Cell::new(0),
+        }
+    }
+
+    /// Create a new [RetryIou] that is already successfully
+    /// initialized with the value `t`. Its initialization
+    /// function will never be called.
+    pub const fn ready(t: T) -> Self {
+        RetryIou {
+            state: RefCell::new(RetryIouState::Init(t)),
+            policy: RetryPolicy::Always,
+            attempts: Cell::new(0),
+        }
+    }
+
+    /// Number of failed initialization attempts so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts.get()
+    }
+}
+
+impl<S, F, T, E> RetryIou<S, F, T, E>
+    where F: FnMut(&mut S) -> Result<T, E>
+{
+    /// Initialize the [RetryIou] if needed and return the
+    /// initialized value or the initialization error,
+    /// consuming the [RetryIou]. At most one initialization
+    /// attempt is made.
+    pub fn try_unwrap(self) -> Result<T, E> {
+        match self.state.into_inner() {
+            RetryIouState::PreInit(mut init, mut f) => f(&mut init),
+            RetryIouState::Init(t) => Ok(t),
+            RetryIouState::Failed(e) => Err(e),
+        }
+    }
+
+    /// Check whether the value has been successfully
+    /// initialized yet.
+    pub fn is_init(&self) -> bool {
+        matches!(&*self.state.borrow(), RetryIouState::Init(_))
+    }
+
+    /// Check whether initialization has failed and will not
+    /// be retried.
+    pub fn is_failed(&self) -> bool {
+        matches!(&*self.state.borrow(), RetryIouState::Failed(_))
+    }
+
+    /// Initialize the [RetryIou] if not yet initialized.
+    /// Returns the error from this attempt if it failed, or
+    /// the recorded error if the [RetryPolicy] has given
+    /// up.
+    ///
+    /// A panic in the initialization function leaves the
+    /// [RetryIou] as it was before the attempt.
+    pub fn try_init(&self) -> Result<(), E>
+        where E: Clone
+    {
+        match &*self.state.borrow() {
+            RetryIouState::Init(_) => return Ok(()),
+            RetryIouState::Failed(e) => return Err(e.clone()),
+            _ => (),
+        }
+        let mut iou = self.state.borrow_mut();
+        match &mut *iou {
+            RetryIouState::Init(_) => Ok(()),
+            RetryIouState::Failed(e) => Err(e.clone()),
+            RetryIouState::PreInit(init, f) => {
+                match f(init) {
+                    Ok(t) => {
+                        *iou = RetryIouState::Init(t);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        let attempts = self.attempts.get() + 1;
+                        self.attempts.set(attempts);
+                        if let RetryPolicy::Attempts(n) = self.policy {
+                            if attempts >= n {
+                                *iou = RetryIouState::Failed(e.clone());
+                            }
+                        }
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Initialize the [RetryIou] if not yet initialized, then
+    /// return a reference to the initialized value or the
+    /// initialization error.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, E>
+        where E: Clone
+    {
+        self.try_init()?;
+        Ok(Ref::map(
+            self.state.borrow(),
+            |s| {
+                match s {
+                    RetryIouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+
+    /// Initialize the [RetryIou] if not yet initialized, then
+    /// return a mutable reference to the initialized value
+    /// or the initialization error.
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, E>
+        where E: Clone
+    {
+        self.try_init()?;
+        Ok(RefMut::map(
+            self.state.borrow_mut(),
+            |s| {
+                match s {
+                    RetryIouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,184 @@
+//! Thread-safe initialize-on-use.
+
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{catch_poison, IouState};
+
+/// Initialize on use, shareable between threads: a value
+/// that will be lazily initialized at first reference.
+///
+/// The initialization function is run exactly once, by
+/// whichever thread first uses the [SyncIou]. Other threads
+/// racing on first use block until initialization is
+/// complete.
+///
+/// If the initialization function panics, the [SyncIou] is
+/// poisoned, as an [Iou](crate::Iou) is: later uses panic
+/// with the message of the original panic.
+pub struct SyncIou<S, F, T>(RwLock<IouState<S, F, T>>);
+
+/// Shared reference to the initialized value of a
+/// [SyncIou], returned by [SyncIou::borrow].
+pub struct SyncIouRef<'a, S, F, T>(RwLockReadGuard<'a, IouState<S, F, T>>);
+
+/// Exclusive reference to the initialized value of a
+/// [SyncIou], returned by [SyncIou::borrow_mut].
+pub struct SyncIouRefMut<'a, S, F, T>(RwLockWriteGuard<'a, IouState<S, F, T>>);
+
+impl<S, F, T> SyncIou<S, F, T> {
+    /// Create a new [SyncIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init`.
+    pub const fn new(init: S, f: F) -> Self {
+        SyncIou(RwLock::new(IouState::PreInit(init, f)))
+    }
+
+    /// Create a new [SyncIou] that is already initialized
+    /// with the value `t`. Its initialization function will
+    /// never be called.
+    pub const fn ready(t: T) -> Self {
+        SyncIou(RwLock::new(IouState::Init(t)))
+    }
+
+    /// Check whether the value has been initialized yet.
+    pub fn is_init(&self) -> bool {
+        matches!(&*self.read(), IouState::Init(_))
+    }
+
+    /// Check whether the initialization function panicked
+    /// during initialization.
+    pub fn is_poisoned(&self) -> bool {
+        matches!(&*self.read(), IouState::Poisoned(_))
+    }
+
+    /// If the [SyncIou] is poisoned and the panic that
+    /// poisoned it had a string message, return a copy of
+    /// that message.
+    pub fn poison_message(&self) -> Option<String> {
+        match &*self.read() {
+            IouState::Poisoned(p) => p.message().map(str::to_string),
+            _ => None,
+        }
+    }
+
+    // Panics are caught and recorded in the state, so a
+    // poisoned lock carries no extra information.
+
+    fn read(&self) -> RwLockReadGuard<'_, IouState<S, F, T>> {
+        self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, IouState<S, F, T>> {
+        self.0.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<S, F, T> SyncIou<S, F, T>
+    where F: FnOnce(S) -> T
+{
+    /// Initialize the [SyncIou] if needed and return the
+    /// initialized value, consuming the [SyncIou].
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn unwrap(self) -> T {
+        match self.0.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            IouState::PreInit(s, f) => f(s),
+            IouState::Init(t) => t,
+            IouState::Poisoned(p) => p.panic("SyncIou"),
+            _ => panic!("SyncIou: corrupted cell"),
+        }
+    }
+
+    /// Initialize the [SyncIou] if not yet initialized. If
+    /// another thread is initializing the [SyncIou], block
+    /// until it is done.
+    ///
+    /// # Panics
+    /// Panics on poisoned cell. A panic in the
+    /// initialization function poisons the cell and is
+    /// propagated.
+    pub fn init(&self) {
+        self.init_here();
+    }
+
+    /// As [SyncIou::init], returning whether this call ran
+    /// the initialization function.
+    pub(crate) fn init_here(&self) -> bool {
+        if self.is_init() {
+            return false;
+        }
+        let mut iou = self.write();
+        // Another thread may have initialized or poisoned the
+        // cell while we were waiting for the lock.
+        match mem::replace(&mut *iou, IouState::Initializing) {
+            IouState::PreInit(s, f) => {
+                let t = catch_poison(|| f(s), |p| *iou = IouState::Poisoned(p));
+                *iou = IouState::Init(t);
+                return true;
+            }
+            state => *iou = state,
+        }
+        if let IouState::Poisoned(p) = &*iou {
+            p.panic("SyncIou");
+        }
+        false
+    }
+
+    /// Initialize the [SyncIou] if not yet initialized, then
+    /// return a shared reference to the initialized value.
+    /// Blocks while some other thread holds a
+    /// [SyncIouRefMut].
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn borrow(&self) -> SyncIouRef<'_, S, F, T> {
+        self.init();
+        SyncIouRef(self.read())
+    }
+
+    /// Initialize the [SyncIou] if not yet initialized, then
+    /// return an exclusive reference to the initialized
+    /// value. Blocks while some other thread holds a
+    /// [SyncIouRef] or [SyncIouRefMut].
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn borrow_mut(&self) -> SyncIouRefMut<'_, S, F, T> {
+        self.init();
+        SyncIouRefMut(self.write())
+    }
+}
+
+impl<S, F, T> Deref for SyncIouRef<'_, S, F, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &*self.0 {
+            IouState::Init(t) => t,
+            _ => panic!("SyncIou: corrupted cell"),
+        }
+    }
+}
+
+impl<S, F, T> Deref for SyncIouRefMut<'_, S, F, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &*self.0 {
+            IouState::Init(t) => t,
+            _ => panic!("SyncIou: corrupted cell"),
+        }
+    }
+}
+
+impl<S, F, T> DerefMut for SyncIouRefMut<'_, S, F, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match &mut *self.0 {
+            IouState::Init(t) => t,
+            _ => panic!("SyncIou: corrupted cell"),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Initialize-on-use with fallible initialization.
+
+use core::cell::{RefCell, Ref, RefMut};
+use core::mem;
+
+use crate::{catch_poison, Poison};
+
+/// Initialize on use, fallibly: a value that will be lazily
+/// initialized at first reference by a function that may
+/// fail.
+///
+/// If initialization fails, the error is recorded in the
+/// [TryIou] and returned by every later access. Accessors
+/// taking `&self` return a clone of the recorded error, and
+/// so require `E: Clone`; wrap the error in an `Rc` or
+/// `Arc` if it is not otherwise clonable.
+///
+/// If the initialization function panics, the [TryIou] is
+/// poisoned, as an [Iou](crate::Iou) is: later uses panic
+/// with the message of the original panic.
+pub struct TryIou<S, F, T, E>(RefCell<TryIouState<S, F, T, E>>);
+
+enum TryIouState<S, F, T, E> {
+    /// Not yet initialized.
+    PreInit(S, F),
+    /// Initialized.
+    Init(T),
+    /// Initialization failed.
+    Failed(E),
+    /// Initialization panicked.
+    Poisoned(Poison),
+}
+
+impl<S, F, T, E> TryIou<S, F, T, E> {
+    /// Create a new [TryIou] that will be initialized on
+    /// first use by applying the function `f` to the
+    /// initialization data `init`.
+    pub const fn new(init: S, f: F) -> Self {
+        TryIou(RefCell::new(TryIouState::PreInit(init, f)))
+    }
+
+    /// Create a new [TryIou] that is already successfully
+    /// initialized with the value `t`. Its initialization
+    /// function will never be called.
+    pub const fn ready(t: T) -> Self {
+        TryIou(RefCell::new(TryIouState::Init(t)))
+    }
+}
+
+impl<S, F, T, E> TryIou<S, F, T, E>
+    where F: FnOnce(S) -> Result<T, E>
+{
+    /// Initialize the [TryIou] if needed and return the
+    /// initialized value or the initialization error,
+    /// consuming the [TryIou].
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn try_unwrap(self) -> Result<T, E> {
+        match self.0.into_inner() {
+            TryIouState::PreInit(s, f) => f(s),
+            TryIouState::Init(t) => Ok(t),
+            TryIouState::Failed(e) => Err(e),
+            TryIouState::Poisoned(p) => p.panic("TryIou"),
+        }
+    }
+
+    /// Check whether the value has been successfully
+    /// initialized yet.
+    pub fn is_init(&self) -> bool {
+        matches!(&*self.0.borrow(), TryIouState::Init(_))
+    }
+
+    /// Check whether initialization has been attempted and
+    /// has failed.
+    pub fn is_failed(&self) -> bool {
+        matches!(&*self.0.borrow(), TryIouState::Failed(_))
+    }
+
+    /// Check whether the initialization function panicked
+    /// during initialization.
+    pub fn is_poisoned(&self) -> bool {
+        matches!(&*self.0.borrow(), TryIouState::Poisoned(_))
+    }
+
+    /// Initialize the [TryIou] if not yet initialized.
+    /// Returns the initialization error if this or an
+    /// earlier initialization failed.
+    ///
+    /// # Panics
+    /// Panics on poisoned cell. A panic in the
+    /// initialization function poisons the cell and is
+    /// propagated.
+    pub fn try_init(&self) -> Result<(), E>
+        where E: Clone
+    {
+        match &*self.0.borrow() {
+            TryIouState::Init(_) => return Ok(()),
+            TryIouState::Failed(e) => return Err(e.clone()),
+            TryIouState::Poisoned(p) => p.panic("TryIou"),
+            TryIouState::PreInit(..) => (),
+        }
+        let mut iou = self.0.borrow_mut();
+        // The cell stays mutably borrowed while the
+        // initialization function runs, and the placeholder
+        // is replaced by the real poison if it panics, so the
+        // placeholder is never seen.
+        match mem::replace(&mut *iou, TryIouState::Poisoned(Poison::default())) {
+            TryIouState::PreInit(s, f) => {
+                match catch_poison(|| f(s), |p| *iou = TryIouState::Poisoned(p)) {
+                    Ok(t) => {
+                        *iou = TryIouState::Init(t);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        *iou = TryIouState::Failed(e.clone());
+                        Err(e)
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Initialize the [TryIou] if not yet initialized, then
+    /// return a reference to the initialized value or the
+    /// initialization error.
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, E>
+        where E: Clone
+    {
+        self.try_init()?;
+        Ok(Ref::map(
+            self.0.borrow(),
+            |s| {
+                match s {
+                    TryIouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+
+    /// Initialize the [TryIou] if not yet initialized, then
+    /// return a mutable reference to the initialized value
+    /// or the initialization error.
+    ///
+    /// # Panics
+    /// Panics on poisoned cell.
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, E>
+        where E: Clone
+    {
+        self.try_init()?;
+        Ok(RefMut::map(
+            self.0.borrow_mut(),
+            |s| {
+                match s {
+                    TryIouState::Init(t) => t,
+                    _ => unreachable!(),
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,104 @@
+//! Initialize-on-use values for every index of a vector.
+
+use alloc::boxed::Box;
+use core::cell::{Cell, OnceCell};
+use core::ops::Range;
+
+/// Initialize on use, by index: a fixed-length vector whose
+/// element at each index is lazily initialized at first
+/// reference, by applying a single initialization function
+/// to the index.
+///
+/// Elements are stored inline, without boxing each one. The
+/// initialization function may reference other elements of
+/// the same [IouVec]. A panic in the initialization function
+/// leaves the element uninitialized, to be retried on next
+/// reference.
+pub struct IouVec<T, F> {
+    cells: Box<[OnceCell<T>]>,
+    f: F,
+    count: Cell<usize>,
+}
+
+impl<T, F> IouVec<T, F> {
+    /// Create a new [IouVec] of `len` elements, each of
+    /// which will be initialized on first use by applying
+    /// the function `f` to its index.
+    pub fn new(len: usize, f: F) -> Self {
+        IouVec {
+            cells: (0..len).map(|_| OnceCell::new()).collect(),
+            f,
+            count: Cell::new(0),
+        }
+    }
+
+    /// Number of elements, initialized or not.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Check whether the [IouVec] has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Check whether the element at index `i` has been
+    /// initialized yet. Returns `false` if `i` is out of
+    /// bounds.
+    pub fn is_init(&self, i: usize) -> bool {
+        self.cells.get(i).is_some_and(|cell| cell.get().is_some())
+    }
+
+    /// Number of elements initialized so far.
+    pub fn init_count(&self) -> usize {
+        self.count.get()
+    }
+
+    /// The initialized elements with their indices, in index
+    /// order. Elements are not initialized by the iteration.
+    pub fn iter_init(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.cells.iter().enumerate().filter_map(|(i, cell)| Some((i, cell.get()?)))
+    }
+}
+
+impl<T, F> IouVec<T, F>
+    where F: Fn(usize) -> T
+{
+    /// Initialize the element at index `i` if not yet
+    /// initialized, then return a reference to it. Returns
+    /// `None` if `i` is out of bounds.
+    ///
+    /// # Panics
+    /// Panics on reentrant use of the same element by the
+    /// initialization function.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        let cell = self.cells.get(i)?;
+        let mut fresh = false;
+        let t = cell.get_or_init(|| {
+            fresh = true;
+            (self.f)(i)
+        });
+        if fresh {
+            self.count.set(self.count.get() + 1);
+        }
+        Some(t)
+    }
+
+    /// Initialize every element in `range` that is not yet
+    /// initialized.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds, or on reentrant
+    /// use of an element by the initialization function.
+    pub fn init_range(&self, range: Range<usize>) {
+        assert!(
+            range.end <= self.len(),
+            "IouVec: range end {} out of bounds for length {}",
+            range.end,
+            self.len(),
+        );
+        for i in range {
+            self.get(i);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Exercise `AsyncIou` with a hand-rolled executor, so that
+//! the order in which awaiters are polled and dropped is
+//! under the test's control.
+
+#![cfg(feature = "std")]
+
+use std::future::{self, Future};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use iou::AsyncIou;
+
+/// Waker that counts how often it is woken.
+#[derive(Default)]
+struct CountWaker(AtomicUsize);
+
+impl CountWaker {
+    fn wakes(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn waker() -> (Arc<CountWaker>, Waker) {
+    let count = Arc::new(CountWaker::default());
+    (Arc::clone(&count), Waker::from(count))
+}
+
+fn poll<F: Future + ?Sized>(fut: Pin<&mut F>, waker: &Waker) -> Poll<F::Output> {
+    fut.poll(&mut Context::from_waker(waker))
+}
+
+/// Switch for an initialization future: `wait` is pending
+/// until `open` is called.
+#[derive(Default)]
+struct Gate {
+    open: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl Gate {
+    fn open(&self) {
+        self.open.store(true, Ordering::SeqCst);
+        if let Some(w) = self.waker.lock().unwrap().take() {
+            w.wake();
+        }
+    }
+
+    async fn wait(&self) {
+        future::poll_fn(|cx| {
+            if self.open.load(Ordering::SeqCst) {
+                return Poll::Ready(());
+            }
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+#[test]
+fn concurrent_awaiters_share_one_initialization() {
+    let gate = Arc::new(Gate::default());
+    let calls = AtomicUsize::new(0);
+    let iou = AsyncIou::new(Arc::clone(&gate), |gate: Arc<Gate>| {
+        calls.fetch_add(1, Ordering::SeqCst);
+        async move {
+            gate.wait().await;
+            42
+        }
+    });
+    let (wakes_a, waker_a) = waker();
+    let (wakes_b, waker_b) = waker();
+    let mut a = Box::pin(iou.get());
+    let mut b = Box::pin(iou.get());
+    assert!(poll(a.as_mut(), &waker_a).is_pending());
+    assert!(poll(b.as_mut(), &waker_b).is_pending());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    gate.open();
+    assert!(wakes_a.wakes() > 0 && wakes_b.wakes() > 0);
+    assert_eq!(poll(b.as_mut(), &waker_b), Poll::Ready(&42));
+    assert_eq!(poll(a.as_mut(), &waker_a), Poll::Ready(&42));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(iou.is_init());
+}
+
+#[test]
+fn dropping_first_awaiter_keeps_initialization() {
+    let gate = Arc::new(Gate::default());
+    let calls = AtomicUsize::new(0);
+    let iou = AsyncIou::new(Arc::clone(&gate), |gate: Arc<Gate>| {
+        calls.fetch_add(1, Ordering::SeqCst);
+        async move {
+            gate.wait().await;
+            42
+        }
+    });
+    let (_, waker_a) = waker();
+    let (wakes_b, waker_b) = waker();
+    let mut a = Box::pin(iou.get());
+    assert!(poll(a.as_mut(), &waker_a).is_pending());
+    drop(a);
+    let mut b = Box::pin(iou.get());
+    assert!(poll(b.as_mut(), &waker_b).is_pending());
+    gate.open();
+    assert!(wakes_b.wakes() > 0);
+    assert_eq!(poll(b.as_mut(), &waker_b), Poll::Ready(&42));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn panic_poisons_and_wakes_awaiters() {
+    let gate = Arc::new(Gate::default());
+    let iou = AsyncIou::new(Arc::clone(&gate), |gate: Arc<Gate>| async move {
+        gate.wait().await;
+        panic!("no value");
+    });
+    let (_, waker_a) = waker();
+    let (wakes_b, waker_b) = waker();
+    let mut a = Box::pin(iou.get());
+    let mut b = Box::pin(iou.get());
+    assert!(poll(a.as_mut(), &waker_a).is_pending());
+    assert!(poll(b.as_mut(), &waker_b).is_pending());
+    gate.open();
+    let r = panic::catch_unwind(AssertUnwindSafe(|| poll(a.as_mut(), &waker_a)));
+    assert!(r.is_err());
+    assert!(wakes_b.wakes() > 0);
+    let e = panic::catch_unwind(AssertUnwindSafe(|| poll(b.as_mut(), &waker_b))).unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<String>().map(String::as_str),
+        Some("AsyncIou: poisoned cell: no value"),
+    );
+    assert!(!iou.is_init());
+}
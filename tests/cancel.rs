@@ -0,0 +1,76 @@
+//! Exercise `CancelIou` timeouts and cancellation across
+//! threads.
+
+#![cfg(feature = "std")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use iou::{init_all, CancelError, CancelIou, CancelToken};
+
+/// Initialization function that hangs on its first run
+/// until told to give up, and succeeds on later runs.
+fn hang_once(
+    runs: &AtomicUsize,
+) -> impl FnMut(u32, &CancelToken) -> Result<u32, u32> + '_ {
+    move |s, token| {
+        if runs.fetch_add(1, Ordering::SeqCst) == 0 {
+            while !token.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            return Err(s);
+        }
+        Ok(2 * s)
+    }
+}
+
+fn wait_for_run(runs: &AtomicUsize) {
+    while runs.load(Ordering::SeqCst) == 0 {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn waiter_takes_over_after_other_deadline() {
+    let runs = AtomicUsize::new(0);
+    let iou = CancelIou::new(21, hang_once(&runs));
+    thread::scope(|scope| {
+        let a = scope.spawn(|| iou.borrow_timeout(Duration::from_millis(200)).copied());
+        wait_for_run(&runs);
+        let b = scope.spawn(|| iou.try_borrow().copied());
+        assert_eq!(a.join().unwrap(), Err(CancelError::TimedOut));
+        assert_eq!(b.join().unwrap(), Ok(42));
+    });
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn cancel_gives_seed_back() {
+    let runs = AtomicUsize::new(0);
+    let iou = CancelIou::new(21, hang_once(&runs));
+    assert!(!iou.cancel());
+    thread::scope(|scope| {
+        let a = scope.spawn(|| iou.try_borrow().copied());
+        wait_for_run(&runs);
+        assert!(iou.cancel());
+        assert_eq!(a.join().unwrap(), Err(CancelError::Cancelled));
+    });
+    assert!(!iou.is_init());
+    assert_eq!(*iou.borrow(), 42);
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn init_all_forces_cancel_ious() {
+    type Cell = CancelIou<u32, fn(u32, &CancelToken) -> Result<u32, u32>, u32>;
+    let double: fn(u32, &CancelToken) -> Result<u32, u32> = |s, _| Ok(2 * s);
+    let mut cells: Vec<Cell> = (0..4).map(|i| CancelIou::new(i, double)).collect();
+    cells.push(CancelIou::ready(7));
+    let refs: Vec<&Cell> = cells.iter().collect();
+    let report = init_all(&refs, 2);
+    assert_eq!((report.initialized, report.skipped), (4, 1));
+    assert!(report.is_ok());
+    assert_eq!(*cells[3].borrow(), 6);
+    assert_eq!(*cells[4].borrow(), 7);
+}
@@ -0,0 +1,58 @@
+//! Exercise the `no_std` core of the crate. A plain `cargo
+//! test` builds the library with `std`; the CI `no_std` job
+//! runs this with `cargo test --no-default-features`, and
+//! also builds the library for a bare-metal target, to check
+//! that it builds and works without `std`.
+
+#![no_std]
+
+use iou::{Iou, IouError, RetryIou, TryIou};
+
+fn double(x: u32) -> u32 {
+    2 * x
+}
+
+#[test]
+fn iou_initializes_once() {
+    let iou = Iou::new(21, double);
+    assert!(!iou.is_init());
+    assert_eq!(*iou.borrow(), 42);
+    *iou.borrow_mut() += 1;
+    assert_eq!(*iou.try_borrow().unwrap(), 43);
+    assert_eq!(iou.unwrap(), 43);
+}
+
+#[test]
+fn iou_reports_conflicting_borrow() {
+    let iou = Iou::new(21, double);
+    let r = iou.borrow_mut();
+    assert_eq!(iou.try_borrow().err(), Some(IouError::AlreadyBorrowed));
+    drop(r);
+    assert!(iou.try_borrow().is_ok());
+}
+
+#[test]
+fn fallible_variants_record_failure() {
+    let iou = TryIou::new(0u32, |x| if x > 0 { Ok(x) } else { Err("zero") });
+    assert_eq!(iou.try_borrow().err(), Some("zero"));
+    assert!(iou.is_failed());
+
+    let iou = RetryIou::new(0u32, |x: &mut u32| {
+        *x += 1;
+        if *x > 1 { Ok(*x) } else { Err(*x) }
+    });
+    assert_eq!(iou.try_borrow().err(), Some(1));
+    assert_eq!(*iou.try_borrow().unwrap(), 2);
+}
+
+#[test]
+fn iou_resets_and_takes() {
+    let iou = Iou::new(1, double);
+    assert_eq!(iou.reset(21), Ok(()));
+    assert_eq!(*iou.borrow(), 42);
+    assert_eq!(iou.reset(5), Err(5));
+    assert_eq!(iou.take(), Some(42));
+    assert_eq!(iou.try_borrow().err(), Some(IouError::Empty));
+    iou.reset_with(5, double);
+    assert_eq!(*iou.borrow(), 10);
+}
@@ -0,0 +1,52 @@
+//! Exercise the thread-safe cells.
+
+#![cfg(feature = "std")]
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+use iou::SyncIou;
+
+#[test]
+fn sync_iou_initializes_once_under_race() {
+    const THREADS: usize = 16;
+    let calls = AtomicUsize::new(0);
+    let iou = SyncIou::new(21u32, |x| {
+        calls.fetch_add(1, Ordering::SeqCst);
+        thread::yield_now();
+        2 * x
+    });
+    let start = Barrier::new(THREADS);
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                start.wait();
+                assert_eq!(*iou.borrow(), 42);
+            });
+        }
+    });
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(iou.is_init());
+}
+
+#[test]
+fn sync_iou_poisons_on_panic() {
+    let iou = SyncIou::new(0u32, |_| -> u32 { panic!("no value") });
+    let r = panic::catch_unwind(|| {
+        iou.init();
+    });
+    assert!(r.is_err());
+    assert!(!iou.is_init());
+    assert!(iou.is_poisoned());
+    assert_eq!(iou.poison_message().as_deref(), Some("no value"));
+    let e = panic::catch_unwind(|| {
+        iou.borrow();
+    })
+    .unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<String>().map(String::as_str),
+        Some("SyncIou: poisoned cell: no value"),
+    );
+}